@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+#[cfg(not(target_family = "wasm"))]
+use std::path::PathBuf;
+use std::time::Duration;
+
+use gpui::Global;
+
+use crate::typing_session::KeystrokeRecord;
+
+pub type Bigram = [char; 2];
+
+/// Intervals kept for each bigram, dropping the oldest ones so that the stats follow recent
+/// typing and the saved timings don't keep growing
+const MAX_SAMPLES: usize = 100;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BigramStats {
+    pub bigram: Bigram,
+    pub samples: usize,
+    pub mean: Duration,
+    pub median: Duration,
+}
+
+/// The latest intervals between consecutive keystrokes, grouped by the pair of characters typed.
+/// This is kept across sessions so that text can be generated to practice slow transitions.
+#[derive(Default)]
+pub struct BigramTimings {
+    intervals: HashMap<Bigram, Vec<Duration>>,
+}
+
+impl Global for BigramTimings {}
+
+impl BigramTimings {
+    /// Reads the timings saved by previous runs, starting empty if there aren't any
+    pub fn load() -> Self {
+        #[cfg(not(target_family = "wasm"))]
+        if let Some(path) = Self::path() {
+            match std::fs::read(&path) {
+                Ok(json) => {
+                    match Self::from_json(&json) {
+                        Some(timings) => return timings,
+                        None => println!("Failed to parse bigram timings `{}`", path.display()),
+                    }
+                }
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+                Err(err) => println!("Failed to read bigram timings `{}`: {err}", path.display()),
+            }
+        }
+
+        Self::default()
+    }
+
+    /// Writes the timings to the config directory for later runs to load
+    pub fn save(&self) {
+        #[cfg(not(target_family = "wasm"))]
+        if let Some(path) = Self::path() {
+            let result = path
+                .parent()
+                .map_or(Ok(()), std::fs::create_dir_all)
+                .and_then(|_| std::fs::write(&path, self.to_json()));
+            if let Err(err) = result {
+                println!("Failed to save bigram timings `{}`: {err}", path.display());
+            }
+        }
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    fn path() -> Option<PathBuf> {
+        crate::config_dir().map(|dir| dir.join("bigram_timings.json"))
+    }
+
+    /// Intervals in microseconds, keyed by the bigram's two characters
+    #[cfg(not(target_family = "wasm"))]
+    fn to_json(&self) -> Vec<u8> {
+        let intervals = self
+            .intervals
+            .iter()
+            .map(|(bigram, intervals)| {
+                let micros = intervals
+                    .iter()
+                    .map(|interval| interval.as_micros() as u64)
+                    .collect::<Vec<_>>();
+                (bigram.iter().collect::<String>(), micros)
+            })
+            .collect::<HashMap<_, _>>();
+        serde_json::to_vec(&intervals).unwrap()
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    fn from_json(json: &[u8]) -> Option<Self> {
+        let entries = serde_json::from_slice::<HashMap<String, Vec<u64>>>(json).ok()?;
+        let intervals = entries
+            .into_iter()
+            .map(|(bigram, micros)| {
+                let mut chars = bigram.chars();
+                let (Some(first), Some(second), None) = (chars.next(), chars.next(), chars.next())
+                else {
+                    return None;
+                };
+                let intervals = micros[micros.len().saturating_sub(MAX_SAMPLES)..]
+                    .iter()
+                    .map(|&micros| Duration::from_micros(micros))
+                    .collect();
+                Some(([first, second], intervals))
+            })
+            .collect::<Option<_>>()?;
+
+        Some(Self { intervals })
+    }
+
+    /// Adds the transitions between consecutive correct keystrokes within words
+    pub fn record(&mut self, keystroke_log: &[KeystrokeRecord]) {
+        for keystrokes in keystroke_log.windows(2) {
+            let [first, second] = keystrokes else {
+                continue;
+            };
+            if !(first.correct && second.correct) {
+                continue;
+            }
+
+            let (Some(first_char), Some(second_char)) = (
+                first.typed.as_deref().and_then(word_char),
+                second.typed.as_deref().and_then(word_char),
+            ) else {
+                continue;
+            };
+
+            // Input methods can commit several characters at once
+            let interval = second.time.saturating_duration_since(first.time);
+            if interval.is_zero() {
+                continue;
+            }
+
+            let intervals = self.intervals.entry([first_char, second_char]).or_default();
+            intervals.push(interval);
+            if intervals.len() > MAX_SAMPLES {
+                intervals.remove(0);
+            }
+        }
+    }
+
+    pub fn stats(&self) -> Vec<BigramStats> {
+        self.intervals
+            .iter()
+            .map(|(&bigram, intervals)| {
+                let mut sorted = intervals.clone();
+                sorted.sort_unstable();
+                let middle = sorted.len() / 2;
+                let median = if sorted.len() % 2 == 0 {
+                    (sorted[middle - 1] + sorted[middle]) / 2
+                } else {
+                    sorted[middle]
+                };
+
+                BigramStats {
+                    bigram,
+                    samples: intervals.len(),
+                    mean: intervals.iter().sum::<Duration>() / intervals.len() as u32,
+                    median,
+                }
+            })
+            .collect()
+    }
+}
+
+/// The lowercased character of a single character grapheme which isn't whitespace
+fn word_char(grapheme: &str) -> Option<char> {
+    let mut chars = grapheme.chars().flat_map(char::to_lowercase);
+    match (chars.next(), chars.next()) {
+        (Some(char), None) if !char.is_whitespace() => Some(char),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use web_time::Instant;
+
+    use super::*;
+    use crate::typing_session::{Input, TypingSession};
+
+    fn type_timed(session: &mut TypingSession, start: Instant, inputs: &[(&str, u64)]) {
+        for &(text, millis) in inputs {
+            let input = match text {
+                "\u{8}" => Input::Backspace,
+                text => Input::Text(text.to_string()),
+            };
+            session.handle_input_at(input, start + Duration::from_millis(millis));
+        }
+    }
+
+    #[test]
+    fn mean_and_median() {
+        let start = Instant::now();
+        let mut session = TypingSession::new("abab ab".to_string());
+        type_timed(
+            &mut session,
+            start,
+            &[
+                ("a", 0),
+                ("b", 100),
+                ("a", 200),
+                ("b", 500),
+                (" ", 600),
+                ("a", 700),
+                ("b", 1300),
+            ],
+        );
+
+        let mut timings = BigramTimings::default();
+        timings.record(session.keystroke_log());
+        let stats = timings.stats();
+        let ab = stats
+            .iter()
+            .find(|stats| stats.bigram == ['a', 'b'])
+            .unwrap();
+        assert_eq!(ab.samples, 3);
+        assert_eq!(ab.mean, Duration::from_millis(1000) / 3);
+        assert_eq!(ab.median, Duration::from_millis(300));
+        assert_eq!(stats.len(), 2);
+    }
+
+    #[test]
+    fn skips_mistakes_and_whitespace() {
+        let start = Instant::now();
+        let mut session = TypingSession::new("ab cd".to_string());
+        type_timed(
+            &mut session,
+            start,
+            &[
+                ("a", 0),
+                ("x", 100),
+                ("\u{8}", 200),
+                ("b", 300),
+                (" ", 400),
+                ("cd", 500),
+            ],
+        );
+
+        let mut timings = BigramTimings::default();
+        timings.record(session.keystroke_log());
+        assert!(timings.stats().is_empty());
+    }
+
+    #[test]
+    fn keeps_latest_samples() {
+        let start = Instant::now();
+        let mut session = TypingSession::new("ab".repeat(MAX_SAMPLES + 1));
+        let inputs = (0..MAX_SAMPLES as u64 + 1)
+            .flat_map(|idx| [("a", idx * 1000), ("b", idx * 1000 + idx + 1)])
+            .collect::<Vec<_>>();
+        type_timed(&mut session, start, &inputs);
+
+        let mut timings = BigramTimings::default();
+        timings.record(session.keystroke_log());
+        let ab = &timings.intervals[&['a', 'b']];
+        assert_eq!(ab.len(), MAX_SAMPLES);
+        assert_eq!(ab[0], Duration::from_millis(2));
+
+        let json = serde_json::to_vec(&HashMap::from([("ab", vec![1; MAX_SAMPLES + 5])])).unwrap();
+        let loaded = BigramTimings::from_json(&json).unwrap();
+        assert_eq!(loaded.intervals[&['a', 'b']].len(), MAX_SAMPLES);
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let mut timings = BigramTimings::default();
+        timings.intervals.insert(
+            ['a', 'b'],
+            vec![Duration::from_millis(120), Duration::from_micros(95)],
+        );
+        timings
+            .intervals
+            .insert(['ש', 'ל'], vec![Duration::from_millis(300)]);
+
+        let loaded = BigramTimings::from_json(&timings.to_json()).unwrap();
+        assert_eq!(loaded.intervals, timings.intervals);
+
+        assert!(BigramTimings::from_json(br#"{"abc": [1]}"#).is_none());
+        assert!(BigramTimings::from_json(b"[]").is_none());
+    }
+}
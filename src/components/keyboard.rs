@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+
+use gpui::prelude::*;
+use gpui::{
+    point, relative, size, AnyElement, App, AvailableSpace, BorderStyle, Bounds, Corners, Edges,
+    Element, ElementId, GlobalElementId, InspectorElementId, LayoutId, PaintQuad, Pixels, Rgba,
+    Size, Style, TextStyleRefinement, Window,
+};
+
+use crate::keyboard::{Key, KeyHint, KeyId, KeyStats, KeyboardLayout};
+use crate::theme::{ActiveTheme, Theme};
+
+const LABEL_AVAILABLE_SPACE: Size<AvailableSpace> = Size {
+    width: AvailableSpace::MinContent,
+    height: AvailableSpace::MinContent,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum HeatmapMetric {
+    ErrorRate,
+    Latency,
+}
+
+/// What the keys of a [`Keyboard`] are coloured by
+pub enum KeyboardOverlay {
+    /// Colours keys from cold to hot by a metric of their stats
+    Heatmap {
+        key_stats: HashMap<KeyId, KeyStats>,
+        metric: HeatmapMetric,
+    },
+    /// Highlights the next key to press and the keys of the finger which presses it
+    Hint(Option<KeyHint>),
+}
+
+pub struct Keyboard {
+    pub layout: KeyboardLayout,
+    pub overlay: KeyboardOverlay,
+}
+
+impl Keyboard {
+    /// Colour of each key, or `None` for keys which are left blank
+    fn key_colors(&self, keys: &[Key], theme: &Theme) -> Vec<Option<Rgba>> {
+        match &self.overlay {
+            KeyboardOverlay::Heatmap { key_stats, metric } => {
+                let values = keys
+                    .iter()
+                    .map(|key| {
+                        let stats = key_stats.get(&key.id)?;
+                        match metric {
+                            HeatmapMetric::ErrorRate => Some(stats.error_rate()),
+                            HeatmapMetric::Latency => {
+                                stats.mean_latency().map(|latency| latency.as_secs_f32())
+                            }
+                        }
+                    })
+                    .collect::<Vec<_>>();
+
+                // Scale the metric so the worst key is fully hot
+                let min_value = match metric {
+                    HeatmapMetric::ErrorRate => 0.0,
+                    HeatmapMetric::Latency => {
+                        values
+                            .iter()
+                            .flatten()
+                            .copied()
+                            .fold(f32::INFINITY, f32::min)
+                    }
+                };
+                let max_value = values.iter().flatten().copied().fold(0.0, f32::max);
+
+                values
+                    .into_iter()
+                    .map(|value| {
+                        let heat = if max_value > min_value {
+                            (value? - min_value) / (max_value - min_value)
+                        } else {
+                            0.0
+                        };
+                        Some(mix(
+                            theme.keyboard_heatmap_cold,
+                            theme.keyboard_heatmap_hot,
+                            heat,
+                        ))
+                    })
+                    .collect()
+            }
+            KeyboardOverlay::Hint(hint) => {
+                keys.iter()
+                    .map(|key| {
+                        let hint = hint.as_ref()?;
+                        if key.id == hint.key || Some(key.id) == hint.shift {
+                            Some(theme.keyboard_hint_key)
+                        } else if key.finger == hint.finger {
+                            Some(theme.keyboard_hint_finger)
+                        } else {
+                            None
+                        }
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+impl IntoElement for Keyboard {
+    type Element = Self;
+
+    fn into_element(self) -> Self::Element {
+        self
+    }
+}
+
+pub struct Prepaint {
+    keys: Vec<(Bounds<Pixels>, Rgba)>,
+    labels: Vec<AnyElement>,
+    corner_radius: Pixels,
+}
+
+impl Element for Keyboard {
+    type PrepaintState = Prepaint;
+    type RequestLayoutState = ();
+
+    fn id(&self) -> Option<ElementId> {
+        None
+    }
+
+    fn source_location(&self) -> Option<&'static core::panic::Location<'static>> {
+        None
+    }
+
+    fn request_layout(
+        &mut self,
+        _id: Option<&GlobalElementId>,
+        _inspector_id: Option<&InspectorElementId>,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> (LayoutId, Self::RequestLayoutState) {
+        let style = Style {
+            size: size(relative(1.0).into(), relative(1.0).into()),
+            ..Default::default()
+        };
+
+        (window.request_layout(style, [], cx), ())
+    }
+
+    fn prepaint(
+        &mut self,
+        _id: Option<&GlobalElementId>,
+        _inspector_id: Option<&InspectorElementId>,
+        bounds: Bounds<Pixels>,
+        _request_layout: &mut Self::RequestLayoutState,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Self::PrepaintState {
+        // Fit the keyboard within the bounds
+        let key_size = (bounds.size.width / KeyboardLayout::WIDTH)
+            .min(bounds.size.height / KeyboardLayout::HEIGHT);
+        let keyboard_size = size(
+            key_size * KeyboardLayout::WIDTH,
+            key_size * KeyboardLayout::HEIGHT,
+        );
+        let origin = bounds.center() - point(keyboard_size.width / 2.0, keyboard_size.height / 2.0);
+        let gap = key_size * 0.08;
+
+        let keys = self.layout.keys();
+        let theme = cx.theme();
+        let key_colors = self.key_colors(&keys, theme);
+
+        let mut labels = Vec::with_capacity(keys.len());
+        let key_bounds = keys
+            .iter()
+            .zip(key_colors)
+            .map(|(key, color)| {
+                let key_bounds = Bounds::new(
+                    origin + point(key_size * key.x, key_size * key.y),
+                    size(key_size * key.width - gap, key_size - gap),
+                );
+                let color = color.unwrap_or(theme.keyboard_key_background);
+
+                let label = key.id.label();
+                if !label.is_empty() {
+                    labels.push((key_bounds, label));
+                }
+
+                (key_bounds, color)
+            })
+            .collect();
+
+        // Key labels
+        let text_color = theme.keyboard_key_text;
+        let labels = labels
+            .into_iter()
+            .map(|(key_bounds, label)| {
+                window.with_text_style(
+                    Some(TextStyleRefinement {
+                        color: Some(text_color.into()),
+                        font_size: Some((key_size * 0.4).into()),
+                        ..Default::default()
+                    }),
+                    |window| {
+                        let mut label = label.into_any_element();
+                        let label_size = label.layout_as_root(LABEL_AVAILABLE_SPACE, window, cx);
+                        label.prepaint_at(
+                            key_bounds.center()
+                                - point(label_size.width / 2.0, label_size.height / 2.0),
+                            window,
+                            cx,
+                        );
+                        label
+                    },
+                )
+            })
+            .collect();
+
+        Prepaint {
+            keys: key_bounds,
+            labels,
+            corner_radius: key_size * 0.15,
+        }
+    }
+
+    fn paint(
+        &mut self,
+        _id: Option<&GlobalElementId>,
+        _inspector_id: Option<&InspectorElementId>,
+        _bounds: Bounds<Pixels>,
+        _request_layout: &mut Self::RequestLayoutState,
+        prepaint: &mut Self::PrepaintState,
+        window: &mut Window,
+        cx: &mut App,
+    ) {
+        for &(bounds, color) in &prepaint.keys {
+            window.paint_quad(PaintQuad {
+                bounds,
+                corner_radii: Corners::all(prepaint.corner_radius),
+                background: color.into(),
+                border_widths: Edges::default(),
+                border_color: gpui::transparent_black(),
+                border_style: BorderStyle::Solid,
+            });
+        }
+
+        for label in &mut prepaint.labels {
+            label.paint(window, cx);
+        }
+    }
+}
+
+fn mix(from: Rgba, to: Rgba, amount: f32) -> Rgba {
+    let amount = amount.clamp(0.0, 1.0);
+    Rgba {
+        r: from.r + (to.r - from.r) * amount,
+        g: from.g + (to.g - from.g) * amount,
+        b: from.b + (to.b - from.b) * amount,
+        a: from.a + (to.a - from.a) * amount,
+    }
+}
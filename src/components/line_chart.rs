@@ -2,7 +2,7 @@ use gpui::prelude::*;
 use gpui::{
     ease_in_out, fill, point, px, relative, size, AnyElement, App, AvailableSpace, BorderStyle,
     Bounds, ContentMask, Corners, Edges, Element, ElementId, GlobalElementId, Hsla,
-    InspectorElementId, LayoutId, PaintQuad, Path, PathBuilder, Pixels, Point, Size, Style,
+    InspectorElementId, LayoutId, PaintQuad, Path, PathBuilder, Pixels, Point, Rgba, Size, Style,
     TextStyleRefinement, Window,
 };
 use web_time::Instant;
@@ -18,7 +18,12 @@ pub struct LineChart {
     pub target_grid_lines_spacing: Pixels,
     pub scale_rounding: f32,
     pub animation_progress: f32,
+    pub series: Vec<LineChartSeries>,
+}
+
+pub struct LineChartSeries {
     pub points: Vec<Point<f32>>,
+    pub color: Rgba,
 }
 
 impl IntoElement for LineChart {
@@ -37,8 +42,13 @@ pub struct Prepaint {
     fractional_grid_line: f32,
 
     y_axis_labels: Vec<AnyElement>,
+    series: Vec<PrepaintSeries>,
+}
+
+struct PrepaintSeries {
     points: Vec<Point<Pixels>>,
     path: Option<Path<Pixels>>,
+    color: Rgba,
 }
 
 impl Element for LineChart {
@@ -81,8 +91,9 @@ impl Element for LineChart {
 
         // Determine scale
         let data_range = self
-            .points
+            .series
             .iter()
+            .flat_map(|series| &series.points)
             .fold(Point::default(), |max, point| point.max(&max));
 
         let target_num_grid_lines =
@@ -178,53 +189,39 @@ impl Element for LineChart {
         content_bounds.size.width -= max_label_width * 2.0;
         content_bounds.origin.x += max_label_width;
 
-        // Create path
+        // Create paths
         let x_denom = data_range.x.max(f32::EPSILON);
         let y_denom = scale.max(f32::EPSILON);
-        let scaled_points: Vec<Point<Pixels>> = self
-            .points
+        let series = self
+            .series
             .iter()
-            .map(|point| {
-                Point {
-                    x: content_bounds.size.width * (point.x / x_denom),
-                    y: content_bounds.size.height * -(point.y / y_denom),
-                } + content_bounds.bottom_left()
+            .map(|series| {
+                let scaled_points: Vec<Point<Pixels>> = series
+                    .points
+                    .iter()
+                    .map(|point| {
+                        Point {
+                            x: content_bounds.size.width * (point.x / x_denom),
+                            y: content_bounds.size.height * -(point.y / y_denom),
+                        } + content_bounds.bottom_left()
+                    })
+                    .collect();
+
+                PrepaintSeries {
+                    path: Some(smooth_path(&scaled_points)),
+                    points: scaled_points,
+                    color: series.color,
+                }
             })
             .collect();
-        let tangents = tangents(&scaled_points);
-
-        let mut path = PathBuilder::stroke(px(2.0));
-        if !scaled_points.is_empty() {
-            path.move_to(scaled_points[0]);
-            for idx in 0..(scaled_points.len().saturating_sub(1)) {
-                let point_a = scaled_points[idx];
-                let point_b = scaled_points[idx + 1];
-                let tangent_a = tangents[idx];
-                let tangent_b = tangents[idx + 1];
-
-                // Control points
-                let segment = point_b - point_a;
-                let dot_a = segment.x.as_f32() * tangent_a.x.as_f32()
-                    + segment.y.as_f32() * tangent_a.y.as_f32();
-                let dot_b = segment.x.as_f32() * tangent_b.x.as_f32()
-                    + segment.y.as_f32() * tangent_b.y.as_f32();
-
-                let smoothing = 1.0 / 3.0;
-                let control_a = point_a + tangent_a * dot_a * smoothing;
-                let control_b = point_b - tangent_b * dot_b * smoothing;
-
-                path.cubic_bezier_to(point_b, control_a, control_b);
-            }
-        }
 
         Prepaint {
             content_bounds,
             num_animation_grid_lines,
             grid_line_spacing,
             fractional_grid_line,
-            points: scaled_points,
-            path: Some(path.build().unwrap()),
             y_axis_labels,
+            series,
         }
     }
 
@@ -268,45 +265,47 @@ impl Element for LineChart {
             ));
         }
 
-        // Path
+        // Paths
         let path_progress =
             ((self.animation_progress - main_line_delay) / (1.0 - main_line_delay)).clamp(0.0, 1.0);
-        window.with_content_mask(
-            Some(ContentMask {
-                bounds: Bounds {
-                    origin: prepaint.content_bounds.origin + point(px(2.0), px(0.0)),
-                    size: size(
-                        prepaint.content_bounds.size.width * path_progress,
-                        prepaint.content_bounds.size.height,
-                    ),
+        for series in &mut prepaint.series {
+            window.with_content_mask(
+                Some(ContentMask {
+                    bounds: Bounds {
+                        origin: prepaint.content_bounds.origin + point(px(2.0), px(0.0)),
+                        size: size(
+                            prepaint.content_bounds.size.width * path_progress,
+                            prepaint.content_bounds.size.height,
+                        ),
+                    },
+                }),
+                |window| {
+                    window.paint_path(series.path.take().unwrap(), series.color);
                 },
-            }),
-            |window| {
-                window.paint_path(prepaint.path.take().unwrap(), cx.theme().base.foreground);
-            },
-        );
-
-        for point in &prepaint.points[1..] {
-            if point.x
-                > prepaint.content_bounds.size.width * path_progress
-                    + prepaint.content_bounds.origin.x
-            {
-                break;
-            }
+            );
 
-            window.paint_quad(PaintQuad {
-                bounds: Bounds::centered_at(*point, size(px(8.0), px(8.0))),
-                corner_radii: Corners {
-                    top_left: px(4.0),
-                    top_right: px(4.0),
-                    bottom_right: px(4.0),
-                    bottom_left: px(4.0),
-                },
-                background: cx.theme().base.foreground.into(),
-                border_widths: Edges::default(),
-                border_color: gpui::transparent_black(),
-                border_style: BorderStyle::Solid,
-            });
+            for point in series.points.iter().skip(1) {
+                if point.x
+                    > prepaint.content_bounds.size.width * path_progress
+                        + prepaint.content_bounds.origin.x
+                {
+                    break;
+                }
+
+                window.paint_quad(PaintQuad {
+                    bounds: Bounds::centered_at(*point, size(px(8.0), px(8.0))),
+                    corner_radii: Corners {
+                        top_left: px(4.0),
+                        top_right: px(4.0),
+                        bottom_right: px(4.0),
+                        bottom_left: px(4.0),
+                    },
+                    background: series.color.into(),
+                    border_widths: Edges::default(),
+                    border_color: gpui::transparent_black(),
+                    border_style: BorderStyle::Solid,
+                });
+            }
         }
 
         // Axes
@@ -331,6 +330,36 @@ impl Element for LineChart {
     }
 }
 
+fn smooth_path(points: &[Point<Pixels>]) -> Path<Pixels> {
+    let tangents = tangents(points);
+
+    let mut path = PathBuilder::stroke(px(2.0));
+    if !points.is_empty() {
+        path.move_to(points[0]);
+        for idx in 0..(points.len().saturating_sub(1)) {
+            let point_a = points[idx];
+            let point_b = points[idx + 1];
+            let tangent_a = tangents[idx];
+            let tangent_b = tangents[idx + 1];
+
+            // Control points
+            let segment = point_b - point_a;
+            let dot_a = segment.x.as_f32() * tangent_a.x.as_f32()
+                + segment.y.as_f32() * tangent_a.y.as_f32();
+            let dot_b = segment.x.as_f32() * tangent_b.x.as_f32()
+                + segment.y.as_f32() * tangent_b.y.as_f32();
+
+            let smoothing = 1.0 / 3.0;
+            let control_a = point_a + tangent_a * dot_a * smoothing;
+            let control_b = point_b - tangent_b * dot_b * smoothing;
+
+            path.cubic_bezier_to(point_b, control_a, control_b);
+        }
+    }
+
+    path.build().unwrap()
+}
+
 fn tangents(points: &[Point<Pixels>]) -> Vec<Point<Pixels>> {
     if points.len() <= 1 {
         return [point(px(1.0), px(0.0))][..points.len()].to_vec();
@@ -1,4 +1,5 @@
 pub mod button;
 pub mod clamp;
 pub mod continuous_animation;
+pub mod keyboard;
 pub mod line_chart;
@@ -1,18 +1,64 @@
+use std::cmp::Reverse;
+#[cfg(not(target_family = "wasm"))]
+use std::path::PathBuf;
+#[cfg(not(target_family = "wasm"))]
+use std::rc::Rc;
 use std::time::Duration;
 
 use gpui::prelude::*;
-use gpui::{div, point, px, Animation, AnimationExt, App, Entity, FocusHandle, Percentage, Window};
+#[cfg(not(target_family = "wasm"))]
+use gpui::PathPromptOptions;
+use gpui::{
+    div, point, px, rems, Animation, AnimationExt, App, ElementId, Entity, FocusHandle, Percentage,
+    Point, Rgba, SharedString, Window,
+};
 
+use crate::bigrams::{BigramStats, BigramTimings};
 use crate::components::clamp::clamp;
-use crate::components::line_chart::LineChart;
-use crate::counter::Counter;
+use crate::components::keyboard::{HeatmapMetric, Keyboard, KeyboardOverlay};
+use crate::components::line_chart::{LineChart, LineChartSeries};
+use crate::counter::{Counter, CounterFinishedEvent, StartCounterEvent};
+use crate::dictionary::Dictionary;
+#[cfg(not(target_family = "wasm"))]
+use crate::keyboard::KeyboardLayout;
+use crate::keyboard::LAYOUTS;
+#[cfg(not(target_family = "wasm"))]
+use crate::settings::StenoMachineSettings;
+use crate::settings::{
+    ActiveSettings, Settings, StenoHintMode, TestLength, TextFont, TextSource, TextViewLayout,
+    CARET_POSITION_PRESETS, LANGUAGES, LETTER_SPACING_PRESETS, STENO_HINT_DELAY_PRESETS,
+    TEXT_SIZE_PRESETS, TIME_PRESETS, VISIBLE_LINE_PRESETS, WORD_PRESETS,
+};
+use crate::steno::{format_outline, StenoDictionary, StenoKeymap, StenoLessons, TapeTranslation};
+#[cfg(not(target_family = "wasm"))]
+use crate::steno::{SerialMachine, StenoProtocol};
 use crate::text_view::TextView;
-use crate::theme::ActiveTheme;
+use crate::theme::{ActiveTheme, Theme};
+use crate::typing_session::WordRecord;
+
+const WORD_LIST_LEN: usize = 5;
+const BIGRAM_TABLE_LEN: usize = 8;
+const STENO_TAPE_LEN: usize = 12;
+const STENO_HINT_LEN: usize = 3;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum BigramColumn {
+    Mean,
+    Median,
+    Samples,
+}
 
 pub struct ContentView {
     text_view: Entity<TextView>,
     counter: Entity<Counter>,
-    wpm_measurements: Option<Vec<f32>>,
+    results: Option<CounterFinishedEvent>,
+    /// Layout to show the heatmap on, or `None` for the layout which was typed on
+    heatmap_layout_idx: Option<usize>,
+    heatmap_metric: HeatmapMetric,
+    bigram_sort: BigramColumn,
+    /// Ports to choose a steno machine from, as of the last scan
+    #[cfg(not(target_family = "wasm"))]
+    serial_ports: Vec<String>,
 }
 
 impl ContentView {
@@ -22,18 +68,940 @@ impl ContentView {
             let counter = Counter::new(text_view.clone(), cx);
 
             cx.subscribe(&counter, |this: &mut Self, _counter, event, cx| {
-                this.wpm_measurements = Some(event.wpm_measurements.clone());
+                this.results = Some(event.clone());
                 cx.notify();
             })
             .detach();
+            // Hides the test length presets
+            cx.subscribe(
+                &text_view,
+                |_this, _text_view, _event: &StartCounterEvent, cx| {
+                    cx.notify();
+                },
+            )
+            .detach();
+            cx.observe(&text_view, |_this, _text_view, cx| {
+                if cx.settings().keyboard_hints || cx.settings().steno.enabled {
+                    cx.notify();
+                }
+            })
+            .detach();
 
             Self {
                 text_view,
                 counter,
-                wpm_measurements: None,
+                results: None,
+                heatmap_layout_idx: None,
+                heatmap_metric: HeatmapMetric::ErrorRate,
+                bigram_sort: BigramColumn::Mean,
+                #[cfg(not(target_family = "wasm"))]
+                serial_ports: SerialMachine::port_names(),
             }
         })
     }
+
+    /// On-screen keyboard showing how to type the next grapheme
+    fn keyboard_hints(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let layout = cx.settings().typing_layout().clone();
+        let hint = self
+            .text_view
+            .read(cx)
+            .session()
+            .next_grapheme()
+            .and_then(|grapheme| layout.hint(grapheme));
+
+        div()
+            .flex()
+            .flex_col()
+            .items_center()
+            .gap_1()
+            .child(div().w(px(480.0)).h(px(160.0)).child(Keyboard {
+                layout,
+                overlay: KeyboardOverlay::Hint(hint),
+            }))
+            .child(
+                div()
+                    .text_sm()
+                    .text_color(cx.theme().results_label_text)
+                    .child(hint.map_or("", |hint| hint.finger.name())),
+            )
+    }
+
+    /// Outlines for the upcoming word, once the hint setting allows them
+    fn steno_hint(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let hints = cx.settings().steno.hints;
+        let text_view = self.text_view.read(cx);
+        let outlines = hints
+            .filter(|&mode| text_view.show_steno_hint(mode))
+            .map(|_| {
+                let word = text_view.upcoming_word();
+                // Lessons teach a particular outline, so it comes first
+                let lesson_outline = match &cx.settings().text_source {
+                    TextSource::StenoLesson(id) => {
+                        cx.global::<StenoLessons>()
+                            .lesson(id)
+                            .and_then(|lesson| lesson.outline(word))
+                    }
+                    TextSource::Dictionary => None,
+                };
+                let mut outlines = cx.global::<StenoDictionary>().outlines(word);
+                if let Some(lesson_outline) = lesson_outline {
+                    outlines.retain(|&outline| outline != lesson_outline);
+                    outlines.insert(0, lesson_outline);
+                }
+                outlines
+                    .into_iter()
+                    .take(STENO_HINT_LEN)
+                    .map(format_outline)
+                    .collect::<Vec<_>>()
+                    .join("  ")
+            });
+
+        div()
+            .h(px(24.0))
+            .text_color(cx.theme().steno_hint_text)
+            .font_family(cx.theme().steno_tape_font_family)
+            .children(outlines)
+    }
+
+    /// The most recent strokes with their translations, newest at the bottom
+    fn steno_tape(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+        let suggestions = cx.settings().steno.suggestions;
+        let tape = self.text_view.read(cx).steno_tape();
+        let entries = &tape[tape.len().saturating_sub(STENO_TAPE_LEN)..];
+
+        div()
+            .flex()
+            .flex_col()
+            .justify_end()
+            .w(px(320.0))
+            .h_full()
+            .overflow_hidden()
+            .text_sm()
+            .font_family(theme.steno_tape_font_family)
+            .children(entries.iter().map(|entry| {
+                div()
+                    .flex()
+                    .gap_2()
+                    .whitespace_nowrap()
+                    .child(
+                        div()
+                            .text_color(theme.steno_tape_stroke)
+                            .child(entry.stroke.paper_tape()),
+                    )
+                    .child(match &entry.translation {
+                        TapeTranslation::Translated(translation) => {
+                            div()
+                                .text_color(theme.steno_tape_translation)
+                                .child(translation.clone())
+                        }
+                        TapeTranslation::Untranslated => {
+                            div()
+                                .text_color(theme.steno_tape_untranslated)
+                                .child("untranslated")
+                        }
+                        TapeTranslation::Undo => {
+                            div().text_color(theme.steno_tape_translation).child("undo")
+                        }
+                    })
+                    .when_some(
+                        entry.suggestion.as_ref().filter(|_| suggestions),
+                        |element, suggestion| {
+                            element.child(
+                                div()
+                                    .text_color(theme.steno_tape_suggestion)
+                                    .child(format!("({})", format_outline(suggestion))),
+                            )
+                        },
+                    )
+            }))
+    }
+
+    /// A setting to pick before the test starts, which applies `update` to the settings and starts
+    /// over with new text
+    fn option(
+        &self,
+        id: impl Into<ElementId>,
+        label: impl Into<SharedString>,
+        selected: bool,
+        update: impl Fn(&mut Settings, &mut App) + 'static,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let theme = cx.theme();
+        div()
+            .id(id)
+            .cursor_pointer()
+            .text_color(if selected {
+                theme.counter_preset_selected_text
+            } else {
+                theme.counter_preset_text
+            })
+            .child(label.into())
+            .on_click(cx.listener(move |this, _event, _window, cx| {
+                cx.update_global(|settings: &mut Settings, cx| update(settings, cx));
+                this.text_view
+                    .update(cx, |text_view, cx| text_view.restart(cx));
+                cx.notify();
+            }))
+    }
+
+    /// Test lengths to choose from before the test starts, followed by controls which shorten or
+    /// lengthen the current test to any length
+    fn test_length_presets(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let current = cx.settings().test_length;
+        let label = |test_length: TestLength| {
+            match test_length {
+                TestLength::Time(seconds) => format!("{seconds}s"),
+                TestLength::Words(words) => format!("{words} words"),
+            }
+        };
+        let presets = TIME_PRESETS
+            .map(TestLength::Time)
+            .into_iter()
+            .chain(WORD_PRESETS.map(TestLength::Words));
+        let custom = presets.clone().all(|test_length| test_length != current);
+        let mut options = presets
+            .enumerate()
+            .map(|(idx, test_length)| {
+                self.option(
+                    ("test-length", idx),
+                    label(test_length),
+                    test_length == current,
+                    move |settings, _cx| settings.test_length = test_length,
+                    cx,
+                )
+                .into_any_element()
+            })
+            .collect::<Vec<_>>();
+
+        options.push(
+            self.option(
+                "test-length-shorter",
+                "-",
+                false,
+                |settings, _cx| settings.test_length = settings.test_length.shorter(),
+                cx,
+            )
+            .into_any_element(),
+        );
+        if custom {
+            options.push(
+                self.option(
+                    "test-length-custom",
+                    label(current),
+                    true,
+                    |_settings, _cx| {},
+                    cx,
+                )
+                .into_any_element(),
+            );
+        }
+        options.push(
+            self.option(
+                "test-length-longer",
+                "+",
+                false,
+                |settings, _cx| settings.test_length = settings.test_length.longer(),
+                cx,
+            )
+            .into_any_element(),
+        );
+
+        div()
+            .flex()
+            .justify_center()
+            .gap_4()
+            .text_sm()
+            .children(options)
+    }
+
+    /// Where the text comes from, chosen before the test starts
+    fn text_source_options(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let settings = cx.settings();
+        let (current_language, current_source) = (settings.language, settings.text_source.clone());
+        let lessons = cx
+            .global::<StenoLessons>()
+            .lessons()
+            .iter()
+            .map(|lesson| (lesson.id.clone(), lesson.name.to_lowercase()))
+            .collect::<Vec<_>>();
+        let mut options = Vec::new();
+
+        for (idx, (language, name)) in LANGUAGES.into_iter().enumerate() {
+            options.push(
+                self.option(
+                    ("language", idx),
+                    name,
+                    current_source == TextSource::Dictionary && current_language == language,
+                    move |settings, cx| {
+                        settings.language = language;
+                        settings.text_source = TextSource::Dictionary;
+                        Dictionary::load_language(language, cx);
+                    },
+                    cx,
+                )
+                .into_any_element(),
+            );
+        }
+        for (idx, (id, name)) in lessons.into_iter().enumerate() {
+            let source = TextSource::StenoLesson(id);
+            options.push(
+                self.option(
+                    ("lesson", idx),
+                    name,
+                    current_source == source,
+                    move |settings, _cx| settings.text_source = source.clone(),
+                    cx,
+                )
+                .into_any_element(),
+            );
+        }
+
+        div()
+            .flex()
+            .justify_center()
+            .gap_4()
+            .text_sm()
+            .children(options)
+    }
+
+    /// How the text is displayed, chosen before the test starts
+    fn text_view_options(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let current = cx.settings().text_view;
+        let mut options = Vec::new();
+
+        for (idx, (font, label)) in [(TextFont::Sans, "sans"), (TextFont::Monospace, "monospace")]
+            .into_iter()
+            .enumerate()
+        {
+            options.push(
+                self.option(
+                    ("text-font", idx),
+                    label,
+                    current.font == font,
+                    move |settings, _cx| settings.text_view.font = font,
+                    cx,
+                )
+                .into_any_element(),
+            );
+        }
+        for (idx, (size, label)) in TEXT_SIZE_PRESETS
+            .into_iter()
+            .zip(["small", "medium", "large"])
+            .enumerate()
+        {
+            options.push(
+                self.option(
+                    ("text-size", idx),
+                    label,
+                    current.text_size == rems(size),
+                    move |settings, _cx| settings.text_view.text_size = rems(size),
+                    cx,
+                )
+                .into_any_element(),
+            );
+        }
+        for (idx, visible_lines) in VISIBLE_LINE_PRESETS.into_iter().enumerate() {
+            let selected = matches!(
+                current.layout,
+                TextViewLayout::Wrapped { visible_lines: lines } if lines == visible_lines
+            );
+            options.push(
+                self.option(
+                    ("visible-lines", idx),
+                    format!(
+                        "{visible_lines} {}",
+                        if visible_lines == 1 { "line" } else { "lines" }
+                    ),
+                    selected,
+                    move |settings, _cx| {
+                        settings.text_view.layout = TextViewLayout::Wrapped { visible_lines }
+                    },
+                    cx,
+                )
+                .into_any_element(),
+            );
+        }
+        for (idx, caret_position) in CARET_POSITION_PRESETS.into_iter().enumerate() {
+            let selected = matches!(
+                current.layout,
+                TextViewLayout::Tape { caret_position: position } if position == caret_position
+            );
+            options.push(
+                self.option(
+                    ("tape", idx),
+                    format!("tape {:.0}%", caret_position * 100.0),
+                    selected,
+                    move |settings, _cx| {
+                        settings.text_view.layout = TextViewLayout::Tape { caret_position }
+                    },
+                    cx,
+                )
+                .into_any_element(),
+            );
+        }
+        for (idx, spacing) in LETTER_SPACING_PRESETS.into_iter().enumerate() {
+            options.push(
+                self.option(
+                    ("letter-spacing", idx),
+                    format!("{spacing}px spacing"),
+                    current.letter_spacing == px(spacing),
+                    move |settings, _cx| settings.text_view.letter_spacing = px(spacing),
+                    cx,
+                )
+                .into_any_element(),
+            );
+        }
+        options.push(
+            self.option(
+                "blind",
+                "blind",
+                current.blind,
+                |settings, _cx| settings.text_view.blind = !settings.text_view.blind,
+                cx,
+            )
+            .into_any_element(),
+        );
+
+        div()
+            .flex()
+            .justify_center()
+            .gap_4()
+            .text_sm()
+            .children(options)
+    }
+
+    /// How keys are typed and what hints are shown for them, chosen before the test starts
+    fn input_options(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let settings = cx.settings();
+        let (keyboard_hints, steno_enabled) = (settings.keyboard_hints, settings.steno.enabled);
+        let options = vec![
+            self.option(
+                "keyboard-hints",
+                "keyboard hints",
+                keyboard_hints,
+                |settings, _cx| settings.keyboard_hints = !settings.keyboard_hints,
+                cx,
+            )
+            .into_any_element(),
+            self.option(
+                "steno",
+                "steno",
+                steno_enabled,
+                |settings, _cx| settings.steno.enabled = !settings.steno.enabled,
+                cx,
+            )
+            .into_any_element(),
+        ];
+
+        div()
+            .flex()
+            .justify_center()
+            .gap_4()
+            .text_sm()
+            .children(options)
+    }
+
+    /// Layout to translate keys to, chosen before the test starts
+    fn emulated_layout_options(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let settings = cx.settings();
+        let system_layout = settings.keyboard_layout;
+        let current = settings.emulated_layout.clone();
+        let custom = settings.custom_layout_path.is_some();
+        let mut options = vec![self
+            .option(
+                "no-emulation",
+                "no emulation",
+                current.is_none(),
+                |settings, _cx| {
+                    settings.emulated_layout = None;
+                    settings.custom_layout_path = None;
+                },
+                cx,
+            )
+            .into_any_element()];
+
+        for (idx, layout) in LAYOUTS.into_iter().enumerate() {
+            if layout == system_layout {
+                continue;
+            }
+            options.push(
+                self.option(
+                    ("emulated-layout", idx),
+                    format!("emulate {}", layout.name.to_lowercase()),
+                    !custom && current.as_ref() == Some(layout),
+                    move |settings, _cx| {
+                        settings.emulated_layout = Some(layout.clone());
+                        settings.custom_layout_path = None;
+                    },
+                    cx,
+                )
+                .into_any_element(),
+            );
+        }
+        #[cfg(not(target_family = "wasm"))]
+        options.push(
+            self.file_option(
+                "custom-layout",
+                "custom layout",
+                custom && current.is_some(),
+                |settings, paths, _cx| {
+                    let Some(path) = paths.into_iter().next() else {
+                        return;
+                    };
+                    match KeyboardLayout::load(&path) {
+                        Some(layout) => {
+                            settings.emulated_layout = Some(layout);
+                            settings.custom_layout_path = Some(path);
+                        }
+                        None => println!("Failed to load keyboard layout `{}`", path.display()),
+                    }
+                },
+                cx,
+            )
+            .into_any_element(),
+        );
+
+        div()
+            .flex()
+            .justify_center()
+            .gap_4()
+            .text_sm()
+            .children(options)
+    }
+
+    /// Like [`Self::option`], but prompts for files to apply `update` with
+    #[cfg(not(target_family = "wasm"))]
+    fn file_option(
+        &self,
+        id: impl Into<ElementId>,
+        label: impl Into<SharedString>,
+        selected: bool,
+        update: impl Fn(&mut Settings, Vec<PathBuf>, &mut App) + 'static,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let update = Rc::new(update);
+        let theme = cx.theme();
+        div()
+            .id(id)
+            .cursor_pointer()
+            .text_color(if selected {
+                theme.counter_preset_selected_text
+            } else {
+                theme.counter_preset_text
+            })
+            .child(label.into())
+            .on_click(cx.listener(move |_this, _event, _window, cx| {
+                let paths = cx.prompt_for_paths(PathPromptOptions {
+                    files: true,
+                    directories: false,
+                    multiple: true,
+                    prompt: None,
+                });
+                let update = update.clone();
+                cx.spawn(async move |this, cx| {
+                    let Ok(Ok(Some(paths))) = paths.await else {
+                        return;
+                    };
+
+                    this.update(cx, |this, cx| {
+                        cx.update_global(|settings: &mut Settings, cx| update(settings, paths, cx));
+                        this.text_view
+                            .update(cx, |text_view, cx| text_view.restart(cx));
+                        cx.notify();
+                    })
+                    .ok();
+                })
+                .detach();
+            }))
+    }
+
+    /// When to show steno hints, which keys write strokes and which dictionaries translate them,
+    /// chosen before the test starts
+    fn steno_options(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let steno = &cx.settings().steno;
+        let (current_hints, dictionary_paths) = (steno.hints, steno.dictionary_paths.clone());
+        let custom_keymap = steno.keymap_path.is_some();
+        let mut options = Vec::new();
+
+        let hint_modes = [(None, "no hints".to_string())]
+            .into_iter()
+            .chain(STENO_HINT_DELAY_PRESETS.map(|seconds| {
+                let delay = Duration::from_secs(seconds);
+                (
+                    Some(StenoHintMode::AfterDelay(delay)),
+                    format!("hints after {seconds}s"),
+                )
+            }))
+            .chain([
+                (
+                    Some(StenoHintMode::AfterMisstroke),
+                    "hints after misstrokes".to_string(),
+                ),
+                (Some(StenoHintMode::Always), "always hint".to_string()),
+            ]);
+        for (idx, (hints, label)) in hint_modes.enumerate() {
+            options.push(
+                self.option(
+                    ("steno-hints", idx),
+                    label,
+                    current_hints == hints,
+                    move |settings, _cx| settings.steno.hints = hints,
+                    cx,
+                )
+                .into_any_element(),
+            );
+        }
+        options.push(
+            self.option(
+                "plover-qwerty-keymap",
+                "plover qwerty keys",
+                !custom_keymap,
+                |settings, _cx| {
+                    settings.steno.keymap = StenoKeymap::plover_qwerty();
+                    settings.steno.keymap_path = None;
+                },
+                cx,
+            )
+            .into_any_element(),
+        );
+        #[cfg(not(target_family = "wasm"))]
+        options.push(
+            self.file_option(
+                "custom-keymap",
+                "custom keys",
+                custom_keymap,
+                |settings, paths, _cx| {
+                    let Some(path) = paths.into_iter().next() else {
+                        return;
+                    };
+                    match StenoKeymap::load(&path) {
+                        Some(keymap) => {
+                            settings.steno.keymap = keymap;
+                            settings.steno.keymap_path = Some(path);
+                        }
+                        None => println!("Failed to load steno keymap `{}`", path.display()),
+                    }
+                },
+                cx,
+            )
+            .into_any_element(),
+        );
+        options.push(
+            self.option(
+                "built-in-dictionary",
+                "built-in dictionary",
+                dictionary_paths.is_empty(),
+                |settings, cx| {
+                    settings.steno.dictionary_paths.clear();
+                    StenoDictionary::load(&[]).set_global(cx);
+                },
+                cx,
+            )
+            .into_any_element(),
+        );
+
+        // Dictionaries which have been added, removing them when clicked
+        for (idx, path) in dictionary_paths.into_iter().enumerate() {
+            let name = path.file_stem().map_or_else(
+                || path.display().to_string(),
+                |name| name.to_string_lossy().into_owned(),
+            );
+            options.push(
+                self.option(
+                    ("dictionary", idx),
+                    name,
+                    true,
+                    move |settings, cx| {
+                        let paths = &mut settings.steno.dictionary_paths;
+                        paths.retain(|added| *added != path);
+                        StenoDictionary::load(paths).set_global(cx);
+                    },
+                    cx,
+                )
+                .into_any_element(),
+            );
+        }
+        #[cfg(not(target_family = "wasm"))]
+        options.push(
+            self.file_option(
+                "add-dictionary",
+                "add dictionary",
+                false,
+                |settings, paths, cx| {
+                    let dictionary_paths = &mut settings.steno.dictionary_paths;
+                    dictionary_paths.extend(paths);
+                    StenoDictionary::load(dictionary_paths).set_global(cx);
+                },
+                cx,
+            )
+            .into_any_element(),
+        );
+
+        div()
+            .flex()
+            .justify_center()
+            .gap_4()
+            .text_sm()
+            .children(options)
+    }
+
+    /// Steno machine to read strokes from, chosen before the test starts
+    #[cfg(not(target_family = "wasm"))]
+    fn machine_options(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let current = cx.settings().steno.machine.clone();
+        let mut ports = self.serial_ports.clone();
+        if let Some(machine) = &current {
+            if !ports.contains(&machine.port) {
+                ports.push(machine.port.clone());
+            }
+        }
+
+        let mut options = vec![self
+            .option(
+                "no-machine",
+                "no machine",
+                current.is_none(),
+                |settings, _cx| settings.steno.machine = None,
+                cx,
+            )
+            .into_any_element()];
+        let protocol = current
+            .as_ref()
+            .map_or(StenoProtocol::GeminiPr, |machine| machine.protocol);
+        for (idx, port) in ports.into_iter().enumerate() {
+            let selected = current.as_ref().is_some_and(|machine| machine.port == port);
+            options.push(
+                self.option(
+                    ("machine-port", idx),
+                    port.clone(),
+                    selected,
+                    move |settings, _cx| {
+                        settings.steno.machine = Some(StenoMachineSettings {
+                            port: port.clone(),
+                            protocol,
+                        });
+                    },
+                    cx,
+                )
+                .into_any_element(),
+            );
+        }
+        if current.is_some() {
+            for (idx, (option_protocol, label)) in [
+                (StenoProtocol::GeminiPr, "gemini pr"),
+                (StenoProtocol::TxBolt, "tx bolt"),
+            ]
+            .into_iter()
+            .enumerate()
+            {
+                options.push(
+                    self.option(
+                        ("machine-protocol", idx),
+                        label,
+                        protocol == option_protocol,
+                        move |settings, _cx| {
+                            if let Some(machine) = &mut settings.steno.machine {
+                                machine.protocol = option_protocol;
+                            }
+                        },
+                        cx,
+                    )
+                    .into_any_element(),
+                );
+            }
+        }
+        options.push(
+            div()
+                .id("scan-ports")
+                .cursor_pointer()
+                .text_color(cx.theme().counter_preset_text)
+                .child("scan ports")
+                .on_click(cx.listener(|this, _event, _window, cx| {
+                    this.serial_ports = SerialMachine::port_names();
+                    cx.notify();
+                }))
+                .into_any_element(),
+        );
+        let error = self.text_view.read(cx).machine_error().map(|error| {
+            div()
+                .text_color(cx.theme().steno_machine_error)
+                .child(error.to_string())
+        });
+
+        div()
+            .flex()
+            .justify_center()
+            .gap_4()
+            .text_sm()
+            .children(options)
+            .children(error)
+    }
+
+    /// Progress through the steno lesson being typed, across all sessions
+    fn lesson_progress(&self, cx: &mut Context<Self>) -> Option<impl IntoElement> {
+        let TextSource::StenoLesson(id) = &cx.settings().text_source else {
+            return None;
+        };
+        let lessons = cx.global::<StenoLessons>();
+        let lesson = lessons.lesson(id)?;
+
+        Some(
+            div()
+                .flex()
+                .justify_center()
+                .gap_8()
+                .text_color(cx.theme().results_label_text)
+                .child(format!(
+                    "{}: {}/{} drills",
+                    lesson.name,
+                    lessons.completed_drills(lesson),
+                    lesson.drills.len()
+                ))
+                .child(format!(
+                    "{}/{} lessons completed",
+                    lessons.completed_lessons(),
+                    lessons.lesson_count()
+                )),
+        )
+    }
+
+    /// Slowest transitions across all sessions, sorted by the selected column
+    fn bigram_table(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let mut bigrams = cx.global::<BigramTimings>().stats();
+        match self.bigram_sort {
+            BigramColumn::Mean => bigrams.sort_by_key(|stats| Reverse(stats.mean)),
+            BigramColumn::Median => bigrams.sort_by_key(|stats| Reverse(stats.median)),
+            BigramColumn::Samples => bigrams.sort_by_key(|stats| Reverse(stats.samples)),
+        }
+        bigrams.truncate(BIGRAM_TABLE_LEN);
+
+        let theme = cx.theme();
+        let (label_color, value_color) = (theme.results_label_text, theme.results_value_text);
+        let column = |title: &'static str,
+                      sort: Option<BigramColumn>,
+                      cell: fn(&BigramStats) -> String,
+                      cx: &mut Context<Self>| {
+            div()
+                .flex()
+                .flex_col()
+                .gap_1()
+                .child(
+                    div()
+                        .id(title)
+                        .text_sm()
+                        .text_color(if sort.is_some() && sort == Some(self.bigram_sort) {
+                            value_color
+                        } else {
+                            label_color
+                        })
+                        .when_some(sort, |element, sort| {
+                            element.cursor_pointer().on_click(cx.listener(
+                                move |this, _event, _window, cx| {
+                                    this.bigram_sort = sort;
+                                    cx.notify();
+                                },
+                            ))
+                        })
+                        .child(title),
+                )
+                .children(
+                    bigrams
+                        .iter()
+                        .map(|stats| div().text_color(value_color).child(cell(stats))),
+                )
+        };
+
+        div()
+            .flex()
+            .gap_4()
+            .child(column(
+                "transition",
+                None,
+                |stats| stats.bigram.iter().collect(),
+                cx,
+            ))
+            .child(column(
+                "mean",
+                Some(BigramColumn::Mean),
+                |stats| format!("{} ms", stats.mean.as_millis()),
+                cx,
+            ))
+            .child(column(
+                "median",
+                Some(BigramColumn::Median),
+                |stats| format!("{} ms", stats.median.as_millis()),
+                cx,
+            ))
+            .child(column(
+                "samples",
+                Some(BigramColumn::Samples),
+                |stats| stats.samples.to_string(),
+                cx,
+            ))
+    }
+
+    fn heatmap_panel(
+        &self,
+        results: &CounterFinishedEvent,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let layout = match self.heatmap_layout_idx {
+            Some(idx) => LAYOUTS[idx].clone(),
+            None => cx.settings().typing_layout().clone(),
+        };
+        let metric_label = match self.heatmap_metric {
+            HeatmapMetric::ErrorRate => "error rate",
+            HeatmapMetric::Latency => "latency",
+        };
+
+        div()
+            .flex()
+            .flex_col()
+            .gap_1()
+            .child(
+                div()
+                    .flex()
+                    .gap_4()
+                    .text_sm()
+                    .text_color(cx.theme().results_label_text)
+                    .child(
+                        div()
+                            .id("heatmap-layout")
+                            .cursor_pointer()
+                            .child(layout.name.to_string())
+                            .on_click(cx.listener(|this, _event, _window, cx| {
+                                this.heatmap_layout_idx = match this.heatmap_layout_idx {
+                                    Some(idx) if idx + 1 < LAYOUTS.len() => Some(idx + 1),
+                                    Some(_) => None,
+                                    None => Some(0),
+                                };
+                                cx.notify();
+                            })),
+                    )
+                    .child(
+                        div()
+                            .id("heatmap-metric")
+                            .cursor_pointer()
+                            .child(metric_label)
+                            .on_click(cx.listener(|this, _event, _window, cx| {
+                                this.heatmap_metric = match this.heatmap_metric {
+                                    HeatmapMetric::ErrorRate => HeatmapMetric::Latency,
+                                    HeatmapMetric::Latency => HeatmapMetric::ErrorRate,
+                                };
+                                cx.notify();
+                            })),
+                    ),
+            )
+            .child(div().w(px(480.0)).h(px(160.0)).child(Keyboard {
+                layout,
+                overlay: KeyboardOverlay::Heatmap {
+                    key_stats: results.key_stats.clone(),
+                    metric: self.heatmap_metric,
+                },
+            }))
+    }
 }
 
 impl Render for ContentView {
@@ -44,50 +1012,307 @@ impl Render for ContentView {
             .size_full()
             .text_color(cx.theme().base.foreground)
             .font_family("Sans")
-            .when(self.wpm_measurements.is_none(), |element| {
+            .when(self.results.is_none(), |element| {
                 element
-                    .child(div().flex_1())
-                    .child(self.text_view.clone())
                     .child(
-                        div().flex_1().flex().flex_col().justify_end().child(
-                            clamp(px(80.0), px(40.0), self.counter.clone())
-                                .vertical()
-                                .position(Percentage(1.0))
-                                .smoothing(10.0),
-                        ),
+                        div()
+                            .flex_1()
+                            .flex()
+                            .flex_col()
+                            .justify_end()
+                            .when(cx.settings().steno.enabled, |element| {
+                                element.child(self.steno_hint(cx))
+                            }),
+                    )
+                    .child(
+                        div()
+                            .flex()
+                            .gap_8()
+                            .child(div().flex_1().child(self.text_view.clone()))
+                            .when(cx.settings().steno.enabled, |element| {
+                                element.child(self.steno_tape(cx))
+                            }),
+                    )
+                    .child(
+                        div()
+                            .flex_1()
+                            .flex()
+                            .flex_col()
+                            .justify_end()
+                            .when(cx.settings().keyboard_hints, |element| {
+                                element.child(self.keyboard_hints(cx))
+                            })
+                            .child(
+                                clamp(px(80.0), px(40.0), self.counter.clone())
+                                    .vertical()
+                                    .position(Percentage(1.0))
+                                    .smoothing(10.0),
+                            )
+                            .when(!self.counter.read(cx).started(), |element| {
+                                element
+                                    .child(self.test_length_presets(cx))
+                                    .child(self.text_source_options(cx))
+                                    .child(self.text_view_options(cx))
+                                    .child(self.input_options(cx))
+                                    .child(self.emulated_layout_options(cx))
+                                    .when(cx.settings().steno.enabled, |element| {
+                                        let element = element.child(self.steno_options(cx));
+                                        #[cfg(not(target_family = "wasm"))]
+                                        let element = element.child(self.machine_options(cx));
+                                        element
+                                    })
+                            }),
                     )
             })
-            .when_some(
-                self.wpm_measurements.as_ref(),
-                |element, wpm_measurements| {
-                    element.child(
-                        clamp(
-                            px(500.0),
-                            px(300.0),
-                            LineChart {
-                                target_grid_lines_spacing: px(64.0),
-                                scale_rounding: 5.0,
-                                animation_progress: 1.0,
-                                points: {
-                                    wpm_measurements
-                                        .iter()
-                                        .enumerate()
-                                        .map(|(idx, &wpm)| point(idx as f32, wpm))
-                                        .collect::<Vec<_>>()
-                                },
-                            }
-                            .with_animation(
-                                "chart",
-                                Animation::new(Duration::from_millis(1500)),
-                                |mut element, progress| {
-                                    element.animation_progress = progress;
-                                    element
-                                },
-                            ),
-                        )
-                        .vertical(),
+            .when_some(self.results.as_ref(), |element, results| {
+                element
+                    .child(
+                        div().flex_1().min_h_0().child(
+                            clamp(
+                                px(500.0),
+                                px(300.0),
+                                LineChart {
+                                    target_grid_lines_spacing: px(64.0),
+                                    scale_rounding: 5.0,
+                                    animation_progress: 1.0,
+                                    series: vec![
+                                        LineChartSeries {
+                                            points: chart_points(&results.raw_wpm_measurements),
+                                            color: cx.theme().results_raw_wpm_line,
+                                        },
+                                        LineChartSeries {
+                                            points: chart_points(&results.net_wpm_measurements),
+                                            color: cx.theme().results_net_wpm_line,
+                                        },
+                                    ],
+                                }
+                                .with_animation(
+                                    "chart",
+                                    Animation::new(Duration::from_millis(1500)),
+                                    |mut element, progress| {
+                                        element.animation_progress = progress;
+                                        element
+                                    },
+                                ),
+                            )
+                            .vertical(),
+                        ),
                     )
-                },
-            )
+                    .child(stats_summary(results, cx.theme()))
+                    .children(self.lesson_progress(cx))
+                    .child(
+                        div()
+                            .flex()
+                            .justify_center()
+                            .gap_16()
+                            .pb_4()
+                            .child(word_lists(&results.words, cx.theme()))
+                            .child(self.bigram_table(cx))
+                            .child(self.heatmap_panel(results, cx)),
+                    )
+            })
     }
 }
+
+fn chart_points(measurements: &[f32]) -> Vec<Point<f32>> {
+    measurements
+        .iter()
+        .enumerate()
+        .map(|(idx, &wpm)| point(idx as f32, wpm))
+        .collect()
+}
+
+fn stats_summary(results: &CounterFinishedEvent, theme: &Theme) -> impl IntoElement {
+    let stats = results.stats;
+    let char_counts = stats.char_counts;
+    div()
+        .flex()
+        .flex_col()
+        .gap_4()
+        .py_4()
+        .child(summary_row(
+            [
+                (
+                    "wpm",
+                    format!("{:.0}", stats.net_wpm(results.duration)),
+                    theme.results_net_wpm_line,
+                ),
+                (
+                    "raw wpm",
+                    format!("{:.0}", stats.raw_wpm(results.duration)),
+                    theme.results_raw_wpm_line,
+                ),
+                (
+                    "accuracy",
+                    format!("{:.0}%", stats.accuracy()),
+                    theme.results_value_text,
+                ),
+                (
+                    "consistency",
+                    format!("{:.0}%", results.consistency),
+                    theme.results_value_text,
+                ),
+                (
+                    "correct words",
+                    stats.correct_words.to_string(),
+                    theme.results_value_text,
+                ),
+            ],
+            theme,
+        ))
+        .child(summary_row(
+            [
+                (
+                    "correct",
+                    char_counts.correct.to_string(),
+                    theme.text_view_correct_text,
+                ),
+                (
+                    "incorrect",
+                    char_counts.incorrect.to_string(),
+                    theme.text_view_incorrect_text,
+                ),
+                (
+                    "extra",
+                    char_counts.extra.to_string(),
+                    theme.text_view_extra_text,
+                ),
+                (
+                    "missed",
+                    char_counts.missed.to_string(),
+                    theme.text_view_missed_text,
+                ),
+                (
+                    "corrected",
+                    stats.corrected_errors.to_string(),
+                    theme.results_value_text,
+                ),
+                (
+                    "keystrokes",
+                    stats.keystrokes.to_string(),
+                    theme.results_value_text,
+                ),
+            ],
+            theme,
+        ))
+        .when_some(results.steno_stats, |element, steno_stats| {
+            element.child(summary_row(
+                [
+                    (
+                        "strokes/min",
+                        format!("{:.0}", steno_stats.strokes_per_minute(results.duration)),
+                        theme.results_value_text,
+                    ),
+                    (
+                        "stroke accuracy",
+                        format!("{:.0}%", steno_stats.accuracy()),
+                        theme.results_value_text,
+                    ),
+                    (
+                        "misstrokes",
+                        steno_stats.misstrokes.to_string(),
+                        theme.results_value_text,
+                    ),
+                    (
+                        "untranslated",
+                        steno_stats.untranslated.to_string(),
+                        theme.results_value_text,
+                    ),
+                    (
+                        "undos",
+                        steno_stats.undos.to_string(),
+                        theme.results_value_text,
+                    ),
+                ],
+                theme,
+            ))
+        })
+}
+
+fn summary_row(
+    items: impl IntoIterator<Item = (&'static str, String, Rgba)>,
+    theme: &Theme,
+) -> impl IntoElement {
+    div()
+        .flex()
+        .justify_center()
+        .gap_8()
+        .children(items.into_iter().map(|(label, value, color)| {
+            div()
+                .flex()
+                .flex_col()
+                .items_center()
+                .child(div().text_2xl().text_color(color).child(value))
+                .child(
+                    div()
+                        .text_sm()
+                        .text_color(theme.results_label_text)
+                        .child(label),
+                )
+        }))
+}
+
+fn word_lists(words: &[WordRecord], theme: &Theme) -> impl IntoElement {
+    let mut slowest = words
+        .iter()
+        .filter(|word| !word.duration.is_zero())
+        .collect::<Vec<_>>();
+    slowest.sort_by(|a, b| a.wpm().total_cmp(&b.wpm()));
+
+    let mut mistyped = words
+        .iter()
+        .filter(|word| word.mistakes != 0)
+        .collect::<Vec<_>>();
+    mistyped.sort_by_key(|word| Reverse(word.mistakes));
+
+    div()
+        .flex()
+        .gap_16()
+        .child(word_list(
+            "slowest words",
+            slowest.into_iter().take(WORD_LIST_LEN).map(|word| {
+                (
+                    word.target.clone(),
+                    format!("{:.0} wpm", word.wpm()),
+                    theme.results_label_text,
+                )
+            }),
+            theme,
+        ))
+        .child(word_list(
+            "most mistyped words",
+            mistyped.into_iter().take(WORD_LIST_LEN).map(|word| {
+                (
+                    word.target.clone(),
+                    word.first_typed.clone(),
+                    theme.text_view_incorrect_text,
+                )
+            }),
+            theme,
+        ))
+}
+
+fn word_list(
+    title: &'static str,
+    rows: impl Iterator<Item = (String, String, Rgba)>,
+    theme: &Theme,
+) -> impl IntoElement {
+    div()
+        .flex()
+        .flex_col()
+        .gap_1()
+        .child(
+            div()
+                .text_sm()
+                .text_color(theme.results_label_text)
+                .child(title),
+        )
+        .children(rows.map(|(word, detail, detail_color)| {
+            div()
+                .flex()
+                .justify_between()
+                .gap_4()
+                .child(div().text_color(theme.results_value_text).child(word))
+                .child(div().text_color(detail_color).child(detail))
+        }))
+}
@@ -1,18 +1,22 @@
+use std::collections::HashMap;
 use std::time::Duration;
 
 use gpui::prelude::*;
 use gpui::{div, App, Entity, EventEmitter, Window};
 use web_time::Instant;
 
+use crate::bigrams::BigramTimings;
+use crate::keyboard::{key_stats, KeyId, KeyStats};
+use crate::settings::{ActiveSettings, TestLength, TextSource};
+use crate::steno::{StenoLessons, StenoStats};
 use crate::text_view::TextView;
 use crate::theme::ActiveTheme;
+use crate::typing_session::{consistency, wpm, SessionStats, WordRecord};
 
-const WPM_CHARS_PER_WORD: f32 = 5.0;
 const NUM_SAMPLES: u32 = 10;
 
 pub struct Counter {
     start_time: Option<Instant>,
-    duration: u64,
 
     text_view: Entity<TextView>,
 }
@@ -30,12 +34,15 @@ impl Counter {
 
             Self {
                 start_time: None,
-                duration: 30,
                 text_view,
             }
         })
     }
 
+    pub fn started(&self) -> bool {
+        self.start_time.is_some()
+    }
+
     /// Starts the counter if not already started
     pub fn start_timer(&mut self, cx: &mut Context<Self>) {
         if self.start_time.is_some() {
@@ -43,37 +50,90 @@ impl Counter {
         }
 
         let start_time = Instant::now();
-        let sample_interval = Duration::from_secs(self.duration) / NUM_SAMPLES;
+        let test_length = cx.settings().test_length;
+        let sample_interval = match test_length {
+            TestLength::Time(seconds) => Duration::from_secs(seconds) / NUM_SAMPLES,
+            // The length isn't known ahead of time, so samples are taken every second
+            TestLength::Words(_) => Duration::from_secs(1),
+        };
         self.start_time = Some(start_time);
 
         cx.spawn(async move |counter, cx| {
-            let mut last_typed_count = 0;
-            let mut wpm_measurements = Vec::with_capacity(NUM_SAMPLES as usize + 1);
+            let mut raw_wpm_measurements = Vec::with_capacity(NUM_SAMPLES as usize);
+            let mut net_wpm_measurements = Vec::with_capacity(NUM_SAMPLES as usize);
+            let mut per_second_wpm = Vec::new();
+            let mut last_second_typed = 0;
 
             let tick_interval = sample_interval.min(Duration::from_millis(100));
             let mut last_sample = Instant::now();
+            let mut last_second = Instant::now();
             cx.background_executor().timer(tick_interval).await;
 
             loop {
                 cx.background_executor().timer(tick_interval).await;
                 let active = counter
                     .update(cx, |counter, cx| {
+                        let stats = counter.text_view.read(cx).session().stats();
+                        let typed = stats.correct_keystrokes + stats.incorrect_keystrokes;
+
+                        if last_second.elapsed() >= Duration::from_secs(1) {
+                            per_second_wpm
+                                .push(wpm(typed - last_second_typed, Duration::from_secs(1)));
+                            last_second_typed = typed;
+                            last_second = last_second + Duration::from_secs(1);
+                        }
+
                         if last_sample.elapsed() >= sample_interval {
-                            let current_typed_count = counter.text_view.read(cx).typed_chars;
-                            wpm_measurements.push(
-                                (current_typed_count - last_typed_count) as f32
-                                    / WPM_CHARS_PER_WORD
-                                    * (60.0 / sample_interval.as_secs_f32()),
-                            );
-                            last_typed_count = current_typed_count;
+                            let elapsed = sample_interval * (raw_wpm_measurements.len() as u32 + 1);
+                            raw_wpm_measurements.push(stats.raw_wpm(elapsed));
+                            net_wpm_measurements.push(stats.net_wpm(elapsed));
                             last_sample = last_sample + sample_interval;
+                        }
 
-                            if wpm_measurements.len() == NUM_SAMPLES as usize {
-                                cx.emit(CounterFinishedEvent {
-                                    wpm_measurements: wpm_measurements.clone(),
+                        let duration = match test_length {
+                            TestLength::Time(_) => {
+                                let finished = raw_wpm_measurements.len() == NUM_SAMPLES as usize;
+                                finished.then(|| sample_interval * NUM_SAMPLES)
+                            }
+                            TestLength::Words(_) => {
+                                let session = counter.text_view.read(cx).session();
+                                (session.head() == session.text().len()).then(|| {
+                                    let elapsed = start_time.elapsed();
+                                    raw_wpm_measurements.push(stats.raw_wpm(elapsed));
+                                    net_wpm_measurements.push(stats.net_wpm(elapsed));
+                                    elapsed
+                                })
+                            }
+                        };
+                        if let Some(duration) = duration {
+                            let text_view = counter.text_view.read(cx);
+                            let steno_stats =
+                                cx.settings().steno.enabled.then(|| text_view.steno_stats());
+                            let session = text_view.session();
+                            let words = session.words().to_vec();
+                            let keystroke_log = session.keystroke_log().to_vec();
+                            let key_stats =
+                                key_stats(&keystroke_log, cx.settings().typing_layout());
+                            cx.update_global(|timings: &mut BigramTimings, _cx| {
+                                timings.record(&keystroke_log);
+                                timings.save();
+                            });
+                            if let TextSource::StenoLesson(id) = cx.settings().text_source.clone() {
+                                cx.update_global(|lessons: &mut StenoLessons, _cx| {
+                                    lessons.record(&id, &words);
                                 });
-                                return false;
                             }
+                            cx.emit(CounterFinishedEvent {
+                                raw_wpm_measurements: raw_wpm_measurements.clone(),
+                                net_wpm_measurements: net_wpm_measurements.clone(),
+                                consistency: consistency(&per_second_wpm),
+                                stats,
+                                words,
+                                key_stats,
+                                steno_stats,
+                                duration,
+                            });
+                            return false;
                         }
 
                         cx.notify();
@@ -92,25 +152,31 @@ impl Counter {
 
 impl Render for Counter {
     fn render(&mut self, _window: &mut Window, cx: &mut Context<'_, Self>) -> impl IntoElement {
-        let remaining = self
-            .start_time
-            .map(|start_time| self.duration.saturating_sub(start_time.elapsed().as_secs()));
+        let progress = self.start_time.map(|start_time| {
+            match cx.settings().test_length {
+                TestLength::Time(seconds) => {
+                    seconds
+                        .saturating_sub(start_time.elapsed().as_secs())
+                        .to_string()
+                }
+                TestLength::Words(words) => {
+                    let finished = self.text_view.read(cx).session().words().len();
+                    format!("{finished}/{words}")
+                }
+            }
+        });
 
         div()
             .flex()
             .size_full()
             .justify_center()
             .text_color(cx.theme().counter_text)
-            .when(remaining.is_none(), |element| {
+            .when(progress.is_none(), |element| {
                 element.text_color(cx.theme().counter_idle_text)
             })
             .text_lg()
             .font_family(cx.theme().counter_font_family)
-            .child(if let Some(remaining) = remaining {
-                format!("{remaining}")
-            } else {
-                cx.theme().counter_idle_message.to_string()
-            })
+            .child(progress.unwrap_or_else(|| cx.theme().counter_idle_message.to_string()))
     }
 }
 
@@ -118,8 +184,19 @@ pub struct StartCounterEvent;
 
 impl EventEmitter<StartCounterEvent> for TextView {}
 
+#[derive(Clone)]
 pub struct CounterFinishedEvent {
-    pub wpm_measurements: Vec<f32>,
+    /// Cumulative raw WPM at the end of each sample interval
+    pub raw_wpm_measurements: Vec<f32>,
+    /// Cumulative net WPM at the end of each sample interval
+    pub net_wpm_measurements: Vec<f32>,
+    pub consistency: f32,
+    pub stats: SessionStats,
+    pub words: Vec<WordRecord>,
+    pub key_stats: HashMap<KeyId, KeyStats>,
+    /// Stroke statistics when typing with steno
+    pub steno_stats: Option<StenoStats>,
+    pub duration: Duration,
 }
 
 impl EventEmitter<CounterFinishedEvent> for Counter {}
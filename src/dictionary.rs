@@ -32,6 +32,12 @@ impl Dictionary {
         cx.set_global(self);
     }
 
+    /// Sets the global dictionary to the words of a language
+    pub fn load_language(language: &str, cx: &mut App) {
+        // Only English plurals can be told apart by a trailing 's'
+        Self::new(language, 250, language == "en").set_global(cx);
+    }
+
     pub fn random_text(word_count: usize, cx: &mut App) -> String {
         let mut rng = rand::rng();
         cx.read_global(|this: &Self, _cx| {
@@ -0,0 +1,601 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::typing_session::KeystrokeRecord;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum KeyId {
+    Char(char),
+    Space,
+    Backspace,
+    LeftShift,
+    RightShift,
+}
+
+impl KeyId {
+    /// Key which types `grapheme` without any modifiers, ignoring case
+    pub fn for_grapheme(grapheme: &str) -> Option<Self> {
+        if !grapheme.is_empty() && grapheme.chars().all(char::is_whitespace) {
+            return Some(Self::Space);
+        }
+
+        let mut chars = grapheme.chars().flat_map(char::to_lowercase);
+        match (chars.next(), chars.next()) {
+            (Some(char), None) => Some(Self::Char(char)),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> String {
+        match self {
+            Self::Char(char) => char.to_string(),
+            Self::Space => String::new(),
+            Self::Backspace => "⌫".to_string(),
+            Self::LeftShift | Self::RightShift => "⇧".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Finger {
+    LeftPinky,
+    LeftRing,
+    LeftMiddle,
+    LeftIndex,
+    Thumb,
+    RightIndex,
+    RightMiddle,
+    RightRing,
+    RightPinky,
+}
+
+impl Finger {
+    /// Finger which presses a key in `row` at `x` when touch typing, following the usual diagonal
+    /// columns of a staggered keyboard
+    fn for_position(row: usize, x: f32) -> Self {
+        const LEFT_PINKY_COLUMNS: [f32; 4] = [1.0, 1.5, 1.75, 2.25];
+
+        match (x - LEFT_PINKY_COLUMNS[row]).round() as i32 {
+            ..=0 => Self::LeftPinky,
+            1 => Self::LeftRing,
+            2 => Self::LeftMiddle,
+            3 | 4 => Self::LeftIndex,
+            5 | 6 => Self::RightIndex,
+            7 => Self::RightMiddle,
+            8 => Self::RightRing,
+            _ => Self::RightPinky,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::LeftPinky => "left pinky",
+            Self::LeftRing => "left ring",
+            Self::LeftMiddle => "left middle",
+            Self::LeftIndex => "left index",
+            Self::Thumb => "thumb",
+            Self::RightIndex => "right index",
+            Self::RightMiddle => "right middle",
+            Self::RightRing => "right ring",
+            Self::RightPinky => "right pinky",
+        }
+    }
+}
+
+/// A key's position on the keyboard, in key widths
+pub struct Key {
+    pub id: KeyId,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub finger: Finger,
+}
+
+/// How to type a grapheme
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct KeyHint {
+    pub key: KeyId,
+    pub finger: Finger,
+    /// Shift key to hold, on the opposite hand to `finger`
+    pub shift: Option<KeyId>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct KeyboardLayout {
+    pub name: Cow<'static, str>,
+    /// Characters on each row from the number row down, typed without shift
+    pub rows: [Cow<'static, str>; 4],
+    /// Characters typed with shift held, matching `rows` key for key
+    pub shifted_rows: [Cow<'static, str>; 4],
+    /// Offset of the first character key on each row
+    pub row_offsets: [f32; 4],
+}
+
+impl KeyboardLayout {
+    pub const HEIGHT: f32 = 5.0;
+    /// Size of the keyboard in key widths
+    pub const WIDTH: f32 = 15.0;
+
+    const fn builtin(
+        name: &'static str,
+        rows: [&'static str; 4],
+        shifted_rows: [&'static str; 4],
+        row_offsets: [f32; 4],
+    ) -> Self {
+        Self {
+            name: Cow::Borrowed(name),
+            rows: [
+                Cow::Borrowed(rows[0]),
+                Cow::Borrowed(rows[1]),
+                Cow::Borrowed(rows[2]),
+                Cow::Borrowed(rows[3]),
+            ],
+            shifted_rows: [
+                Cow::Borrowed(shifted_rows[0]),
+                Cow::Borrowed(shifted_rows[1]),
+                Cow::Borrowed(shifted_rows[2]),
+                Cow::Borrowed(shifted_rows[3]),
+            ],
+            row_offsets,
+        }
+    }
+
+    pub fn keys(&self) -> Vec<Key> {
+        let mut keys = self
+            .rows
+            .iter()
+            .zip(self.row_offsets)
+            .enumerate()
+            .flat_map(|(row_idx, (row, row_offset))| {
+                row.chars().enumerate().map(move |(idx, char)| {
+                    let x = row_offset + idx as f32;
+                    Key {
+                        id: KeyId::Char(char),
+                        x,
+                        y: row_idx as f32,
+                        width: 1.0,
+                        finger: Finger::for_position(row_idx, x),
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let number_row_len = self.rows[0].chars().count() as f32;
+        keys.push(Key {
+            id: KeyId::Backspace,
+            x: number_row_len,
+            y: 0.0,
+            width: Self::WIDTH - number_row_len,
+            finger: Finger::RightPinky,
+        });
+        let bottom_row_end = self.row_offsets[3] + self.rows[3].chars().count() as f32;
+        keys.push(Key {
+            id: KeyId::LeftShift,
+            x: 0.0,
+            y: 3.0,
+            width: self.row_offsets[3],
+            finger: Finger::LeftPinky,
+        });
+        keys.push(Key {
+            id: KeyId::RightShift,
+            x: bottom_row_end,
+            y: 3.0,
+            width: Self::WIDTH - bottom_row_end,
+            finger: Finger::RightPinky,
+        });
+        keys.push(Key {
+            id: KeyId::Space,
+            x: 3.75,
+            y: 4.0,
+            width: 6.25,
+            finger: Finger::Thumb,
+        });
+
+        keys
+    }
+
+    /// Reads a layout from a name followed by its four rows and then its four shifted rows, each on
+    /// their own line. Blank lines are ignored.
+    pub fn parse(source: &str) -> Option<Self> {
+        let mut lines = source
+            .lines()
+            .map(|line| line.trim_end_matches('\r'))
+            .filter(|line| !line.trim().is_empty());
+        let name = lines.next()?.trim();
+        let mut rows = [""; 8];
+        for row in &mut rows {
+            *row = lines.next()?;
+        }
+        if lines.next().is_some() {
+            return None;
+        }
+
+        let owned = |row: &str| Cow::Owned(row.to_string());
+        let layout = Self {
+            name: owned(name),
+            rows: [rows[0], rows[1], rows[2], rows[3]].map(owned),
+            shifted_rows: [rows[4], rows[5], rows[6], rows[7]].map(owned),
+            row_offsets: ANSI_ROW_OFFSETS,
+        };
+        let fits = layout
+            .keys()
+            .iter()
+            .all(|key| key.width > 0.0 && key.x + key.width <= Self::WIDTH);
+        let shifted_rows_match = layout
+            .rows
+            .iter()
+            .zip(&layout.shifted_rows)
+            .all(|(row, shifted_row)| row.chars().count() == shifted_row.chars().count());
+
+        (fits && shifted_rows_match).then_some(layout)
+    }
+
+    /// Reads a layout from a file in the format of [`Self::parse`]
+    pub fn load(path: &Path) -> Option<Self> {
+        Self::parse(&std::fs::read_to_string(path).ok()?)
+    }
+
+    /// Character typed by the key at the same position on `emulated` as the key labelled `key` on
+    /// this layout, translating keys for layouts which the system isn't set to
+    pub fn emulate(&self, emulated: &KeyboardLayout, key: char, shift: bool) -> Option<char> {
+        let key = key.to_lowercase().next()?;
+        self.rows
+            .iter()
+            .zip(emulated.rows.iter().zip(&emulated.shifted_rows))
+            .find_map(|(row, (emulated_row, emulated_shifted_row))| {
+                let idx = row.chars().position(|char| char == key)?;
+                if shift {
+                    emulated_shifted_row.chars().nth(idx)
+                } else {
+                    emulated_row.chars().nth(idx)
+                }
+            })
+    }
+
+    /// Key and finger which type `grapheme`, or `None` if it isn't on the layout
+    pub fn hint(&self, grapheme: &str) -> Option<KeyHint> {
+        if KeyId::for_grapheme(grapheme) == Some(KeyId::Space) {
+            return Some(KeyHint {
+                key: KeyId::Space,
+                finger: Finger::Thumb,
+                shift: None,
+            });
+        }
+
+        let mut chars = grapheme.chars();
+        let (Some(char), None) = (chars.next(), chars.next()) else {
+            return None;
+        };
+
+        self.keys().into_iter().find_map(|key| {
+            let KeyId::Char(unshifted) = key.id else {
+                return None;
+            };
+            let row = key.y as usize;
+            let idx = (key.x - self.row_offsets[row]).round() as usize;
+            let shifted = self.shifted_rows[row].chars().nth(idx);
+
+            let shift = if char == unshifted {
+                None
+            } else if Some(char) == shifted {
+                Some(match key.finger {
+                    Finger::LeftPinky
+                    | Finger::LeftRing
+                    | Finger::LeftMiddle
+                    | Finger::LeftIndex => KeyId::RightShift,
+                    _ => KeyId::LeftShift,
+                })
+            } else {
+                return None;
+            };
+
+            Some(KeyHint {
+                key: key.id,
+                finger: key.finger,
+                shift,
+            })
+        })
+    }
+}
+
+const ANSI_ROW_OFFSETS: [f32; 4] = [0.0, 1.5, 1.75, 2.25];
+
+pub const QWERTY: KeyboardLayout = KeyboardLayout::builtin(
+    "QWERTY",
+    [
+        "`1234567890-=",
+        "qwertyuiop[]\\",
+        "asdfghjkl;'",
+        "zxcvbnm,./",
+    ],
+    [
+        "~!@#$%^&*()_+",
+        "QWERTYUIOP{}|",
+        "ASDFGHJKL:\"",
+        "ZXCVBNM<>?",
+    ],
+    ANSI_ROW_OFFSETS,
+);
+
+pub const DVORAK: KeyboardLayout = KeyboardLayout::builtin(
+    "Dvorak",
+    [
+        "`1234567890[]",
+        "',.pyfgcrl/=\\",
+        "aoeuidhtns-",
+        ";qjkxbmwvz",
+    ],
+    [
+        "~!@#$%^&*(){}",
+        "\"<>PYFGCRL?+|",
+        "AOEUIDHTNS_",
+        ":QJKXBMWVZ",
+    ],
+    ANSI_ROW_OFFSETS,
+);
+
+pub const COLEMAK: KeyboardLayout = KeyboardLayout::builtin(
+    "Colemak",
+    [
+        "`1234567890-=",
+        "qwfpgjluy;[]\\",
+        "arstdhneio'",
+        "zxcvbkm,./",
+    ],
+    [
+        "~!@#$%^&*()_+",
+        "QWFPGJLUY:{}|",
+        "ARSTDHNEIO\"",
+        "ZXCVBKM<>?",
+    ],
+    ANSI_ROW_OFFSETS,
+);
+
+pub const COLEMAK_DH: KeyboardLayout = KeyboardLayout::builtin(
+    "Colemak-DH",
+    [
+        "`1234567890-=",
+        "qwfpbjluy;[]\\",
+        "arstgmneio'",
+        "zxcdvkh,./",
+    ],
+    [
+        "~!@#$%^&*()_+",
+        "QWFPBJLUY:{}|",
+        "ARSTGMNEIO\"",
+        "ZXCDVKH<>?",
+    ],
+    ANSI_ROW_OFFSETS,
+);
+
+pub const WORKMAN: KeyboardLayout = KeyboardLayout::builtin(
+    "Workman",
+    [
+        "`1234567890-=",
+        "qdrwbjfup;[]\\",
+        "ashtgyneoi'",
+        "zxmcvkl,./",
+    ],
+    [
+        "~!@#$%^&*()_+",
+        "QDRWBJFUP:{}|",
+        "ASHTGYNEOI\"",
+        "ZXMCVKL<>?",
+    ],
+    ANSI_ROW_OFFSETS,
+);
+
+pub const AZERTY: KeyboardLayout = KeyboardLayout::builtin(
+    "AZERTY",
+    [
+        "²&é\"'(-è_çà)=",
+        "azertyuiop^$",
+        "qsdfghjklmù*",
+        "<wxcvbn,;:!",
+    ],
+    [
+        "³1234567890°+",
+        "AZERTYUIOP¨£",
+        "QSDFGHJKLM%µ",
+        ">WXCVBN?./§",
+    ],
+    [0.0, 1.5, 1.75, 1.25],
+);
+
+pub const LAYOUTS: [&KeyboardLayout; 6] =
+    [&QWERTY, &DVORAK, &COLEMAK, &COLEMAK_DH, &WORKMAN, &AZERTY];
+
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct KeyStats {
+    pub presses: usize,
+    pub errors: usize,
+    total_latency: Duration,
+    timed_presses: u32,
+}
+
+impl KeyStats {
+    pub fn error_rate(&self) -> f32 {
+        if self.presses == 0 {
+            return 0.0;
+        }
+
+        self.errors as f32 / self.presses as f32
+    }
+
+    /// Mean time since the previous keystroke
+    pub fn mean_latency(&self) -> Option<Duration> {
+        (self.timed_presses != 0).then(|| self.total_latency / self.timed_presses)
+    }
+}
+
+/// Collects stats for the key on `layout` which should have been pressed at each keystroke, timing
+/// each one from the keystroke before it
+pub fn key_stats(
+    keystroke_log: &[KeystrokeRecord],
+    layout: &KeyboardLayout,
+) -> HashMap<KeyId, KeyStats> {
+    let mut key_stats = HashMap::<KeyId, KeyStats>::new();
+    let mut last_time = None;
+
+    for keystroke in keystroke_log {
+        let key = match &keystroke.typed {
+            Some(_) => {
+                keystroke.expected.as_deref().and_then(|expected| {
+                    // Shifted characters are counted for the key they're typed with
+                    layout
+                        .hint(expected)
+                        .map(|hint| hint.key)
+                        .or_else(|| KeyId::for_grapheme(expected))
+                })
+            }
+            None => Some(KeyId::Backspace),
+        };
+
+        if let Some(key) = key {
+            let stats = key_stats.entry(key).or_default();
+            stats.presses += 1;
+            if keystroke.typed.is_some() && !keystroke.correct {
+                stats.errors += 1;
+            }
+            if let Some(last_time) = last_time {
+                stats.total_latency += keystroke.time.saturating_duration_since(last_time);
+                stats.timed_presses += 1;
+            }
+        }
+
+        last_time = Some(keystroke.time);
+    }
+
+    key_stats
+}
+
+#[cfg(test)]
+mod tests {
+    use web_time::Instant;
+
+    use super::*;
+    use crate::typing_session::{Input, TypingSession};
+
+    #[test]
+    fn key_for_grapheme() {
+        assert_eq!(KeyId::for_grapheme("A"), Some(KeyId::Char('a')));
+        assert_eq!(KeyId::for_grapheme(" "), Some(KeyId::Space));
+        assert_eq!(KeyId::for_grapheme("👍🏽"), None);
+        assert_eq!(KeyId::for_grapheme(""), None);
+    }
+
+    #[test]
+    fn rows_fit_keyboard_width() {
+        for layout in LAYOUTS {
+            for key in layout.keys() {
+                assert!(
+                    key.x + key.width <= KeyboardLayout::WIDTH,
+                    "{}",
+                    layout.name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn shifted_rows_match_rows() {
+        for layout in LAYOUTS {
+            for (row, shifted_row) in layout.rows.iter().zip(&layout.shifted_rows) {
+                assert_eq!(
+                    row.chars().count(),
+                    shifted_row.chars().count(),
+                    "{}",
+                    layout.name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn hints() {
+        let hint = |grapheme| {
+            QWERTY
+                .hint(grapheme)
+                .map(|hint| (hint.key, hint.finger, hint.shift))
+        };
+        assert_eq!(hint("f"), Some((KeyId::Char('f'), Finger::LeftIndex, None)));
+        assert_eq!(
+            hint("A"),
+            Some((KeyId::Char('a'), Finger::LeftPinky, Some(KeyId::RightShift)))
+        );
+        assert_eq!(
+            hint("?"),
+            Some((KeyId::Char('/'), Finger::RightPinky, Some(KeyId::LeftShift)))
+        );
+        assert_eq!(
+            hint("6"),
+            Some((KeyId::Char('6'), Finger::RightIndex, None))
+        );
+        assert_eq!(hint(" "), Some((KeyId::Space, Finger::Thumb, None)));
+        assert_eq!(hint("é"), None);
+    }
+
+    #[test]
+    fn emulate_layouts() {
+        assert_eq!(QWERTY.emulate(&DVORAK, 'd', false), Some('e'));
+        assert_eq!(QWERTY.emulate(&DVORAK, 'q', true), Some('"'));
+        assert_eq!(QWERTY.emulate(&COLEMAK_DH, 'g', false), Some('g'));
+        assert_eq!(QWERTY.emulate(&COLEMAK_DH, 'b', true), Some('V'));
+        assert_eq!(QWERTY.emulate(&WORKMAN, 'e', false), Some('r'));
+        assert_eq!(QWERTY.emulate(&WORKMAN, 'é', false), None);
+    }
+
+    #[test]
+    fn parse_layout() {
+        let layout = KeyboardLayout::parse(
+            "Reversed\n\n=-0987654321`\n\\][poiuytrewq\n';lkjhgfdsa\n/.,mnbvcxz\n\n\
+             +_)(*&^%$#@!~\n|}{POIUYTREWQ\n\":LKJHGFDSA\n?><MNBVCXZ\n",
+        )
+        .unwrap();
+        assert_eq!(layout.name, "Reversed");
+        assert_eq!(QWERTY.emulate(&layout, 'a', true), Some('"'));
+
+        assert!(KeyboardLayout::parse("Short\nabc\n").is_none());
+        assert!(KeyboardLayout::parse("Mismatched\n1\nq\na\nz\n!\nQ\nAS\nZ").is_none());
+    }
+
+    #[test]
+    fn stats_from_log() {
+        let start = Instant::now();
+        let mut session = TypingSession::new("aa b".to_string());
+        session.handle_input_at(Input::Text("a".to_string()), start);
+        session.handle_input_at(Input::Text("x".to_string()), start + Duration::from_secs(1));
+        session.handle_input_at(Input::Backspace, start + Duration::from_secs(2));
+        session.handle_input_at(Input::Text("a".to_string()), start + Duration::from_secs(5));
+
+        let key_stats = key_stats(session.keystroke_log(), &QWERTY);
+        let a = key_stats[&KeyId::Char('a')];
+        assert_eq!(a.presses, 3);
+        assert_eq!(a.errors, 1);
+        assert_eq!(a.error_rate(), 1.0 / 3.0);
+        assert_eq!(a.mean_latency(), Some(Duration::from_secs(2)));
+        assert_eq!(key_stats[&KeyId::Backspace].presses, 1);
+    }
+
+    #[test]
+    fn stats_for_shifted_keys() {
+        let start = Instant::now();
+        let mut session = TypingSession::new("A? :".to_string());
+        for (idx, char) in "A? ;".chars().enumerate() {
+            session.handle_input_at(
+                Input::Text(char.to_string()),
+                start + Duration::from_secs(idx as u64),
+            );
+        }
+
+        let key_stats = key_stats(session.keystroke_log(), &QWERTY);
+        assert_eq!(key_stats[&KeyId::Char('a')].presses, 1);
+        assert_eq!(key_stats[&KeyId::Char('/')].presses, 1);
+        assert_eq!(key_stats[&KeyId::Char(';')].presses, 1);
+        assert_eq!(key_stats[&KeyId::Char(';')].errors, 1);
+        assert!(!key_stats.contains_key(&KeyId::Char('?')));
+        assert!(!key_stats.contains_key(&KeyId::Char(':')));
+    }
+}
@@ -1,7 +1,11 @@
 #![cfg_attr(target_family = "wasm", no_main)]
 
+#[cfg(not(target_family = "wasm"))]
+use std::path::PathBuf;
+
 #[cfg(not(target_family = "wasm"))]
 use assets::Assets;
+use bigrams::BigramTimings;
 use components::clamp::clamp;
 use content_view::ContentView;
 use dictionary::Dictionary;
@@ -10,26 +14,52 @@ use gpui::prelude::*;
 use gpui::WindowOptions;
 use gpui::{div, px, App, Entity, FocusHandle, MouseButton, Window};
 use gpui_platform::application;
+use keyboard::KeyboardLayout;
+use settings::{Settings, TextSource};
+use steno::{StenoDictionary, StenoKeymap, StenoLessons};
 use theme::{ActiveTheme, BaseTheme, Theme};
 #[cfg(not(target_family = "wasm"))]
 use window::StenoWindow;
 
 #[cfg(not(target_family = "wasm"))]
 mod assets;
+mod bigrams;
 pub mod components;
 mod content_view;
 mod counter;
 mod cursor;
 mod dictionary;
+mod keyboard;
+mod settings;
+mod steno;
 mod text_view;
 mod theme;
 #[cfg(not(target_family = "wasm"))]
 mod titlebar;
+mod typing_session;
 #[cfg(not(target_family = "wasm"))]
 mod window;
 
 pub const APP_ID: &str = "com.github.someone13574.steno";
 
+/// Directory which stats are kept in between runs
+#[cfg(not(target_family = "wasm"))]
+pub fn config_dir() -> Option<PathBuf> {
+    let home = || std::env::var_os("HOME").map(PathBuf::from);
+    let base = if cfg!(target_os = "windows") {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        home().map(|home| home.join("Library/Application Support"))
+    } else {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| home().map(|home| home.join(".config")))
+    };
+
+    base.map(|dir| dir.join(APP_ID))
+}
+
 pub struct MainView {
     content_view: Entity<ContentView>,
     focus_handle: FocusHandle,
@@ -67,7 +97,37 @@ impl Render for MainView {
 
 fn init_globals(cx: &mut App) {
     cx.set_global(Theme::from(BaseTheme::default_dark()));
-    Dictionary::new("en", 250, true).set_global(cx);
+    let mut settings = Settings::default();
+    if let Some(path) = settings.custom_layout_path.clone() {
+        match KeyboardLayout::load(&path) {
+            Some(layout) => settings.emulated_layout = Some(layout),
+            None => {
+                println!("Failed to load keyboard layout `{}`", path.display());
+                settings.custom_layout_path = None;
+            }
+        }
+    }
+    if let Some(path) = settings.steno.keymap_path.clone() {
+        match StenoKeymap::load(&path) {
+            Some(keymap) => settings.steno.keymap = keymap,
+            None => {
+                println!("Failed to load steno keymap `{}`", path.display());
+                settings.steno.keymap_path = None;
+            }
+        }
+    }
+    let lessons = StenoLessons::load();
+    if let TextSource::StenoLesson(id) = &settings.text_source {
+        if lessons.lesson(id).is_none() {
+            println!("Failed to find steno lesson `{id}`");
+            settings.text_source = TextSource::Dictionary;
+        }
+    }
+    lessons.set_global(cx);
+    cx.set_global(BigramTimings::load());
+    Dictionary::load_language(settings.language, cx);
+    StenoDictionary::load(&settings.steno.dictionary_paths).set_global(cx);
+    cx.set_global(settings);
 }
 
 #[cfg(target_family = "wasm")]
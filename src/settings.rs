@@ -0,0 +1,233 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use gpui::{px, rems, App, Context, Global, Pixels, Rems};
+
+use crate::keyboard::{KeyboardLayout, QWERTY};
+use crate::steno::{StenoKeymap, StenoProtocol};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TextFont {
+    Sans,
+    Monospace,
+}
+
+impl TextFont {
+    pub fn family(self) -> &'static str {
+        match self {
+            Self::Sans => "Sans",
+            Self::Monospace => "Monospace",
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum TextViewLayout {
+    /// Wrapped text which scrolls vertically
+    Wrapped { visible_lines: usize },
+    /// A single unwrapped line which scrolls horizontally, keeping the caret at
+    /// `caret_position` across the view's width
+    Tape { caret_position: f32 },
+}
+
+impl TextViewLayout {
+    /// Line which the head is kept on while scrolling, leaving context above it
+    pub fn active_line(&self) -> usize {
+        match self {
+            Self::Wrapped { visible_lines } => visible_lines.saturating_sub(1) / 2,
+            Self::Tape { .. } => 0,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct TextViewSettings {
+    /// Hides mistakes while typing, leaving them to the results
+    pub blind: bool,
+    pub font: TextFont,
+    pub layout: TextViewLayout,
+    pub text_size: Rems,
+    /// Space added after each glyph
+    pub letter_spacing: Pixels,
+}
+
+impl Default for TextViewSettings {
+    fn default() -> Self {
+        Self {
+            blind: false,
+            font: TextFont::Sans,
+            layout: TextViewLayout::Wrapped { visible_lines: 3 },
+            text_size: rems(1.875),
+            letter_spacing: px(0.0),
+        }
+    }
+}
+
+/// When to show outlines for the upcoming word
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum StenoHintMode {
+    Always,
+    /// Once there hasn't been a stroke for the delay
+    AfterDelay(Duration),
+    /// Once a stroke has been wrong, until one is right
+    AfterMisstroke,
+}
+
+/// A steno machine connected through a serial port
+#[derive(Clone, PartialEq, Eq)]
+pub struct StenoMachineSettings {
+    pub port: String,
+    pub protocol: StenoProtocol,
+}
+
+#[derive(Clone)]
+pub struct StenoSettings {
+    /// Treats the keyboard as a steno machine, typing a stroke for each chord
+    pub enabled: bool,
+    pub keymap: StenoKeymap,
+    /// File which `keymap` was read from, in the format of [`StenoKeymap::parse`]
+    pub keymap_path: Option<PathBuf>,
+    /// Machine to read strokes from alongside the keyboard
+    pub machine: Option<StenoMachineSettings>,
+    /// Shows dictionary outlines for the upcoming word above the text
+    pub hints: Option<StenoHintMode>,
+    /// Shows shorter outlines for words on the paper tape
+    pub suggestions: bool,
+    /// Plover JSON dictionaries to load after the built-in one, taking priority over it
+    pub dictionary_paths: Vec<PathBuf>,
+}
+
+impl Default for StenoSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            keymap: StenoKeymap::plover_qwerty(),
+            keymap_path: None,
+            machine: None,
+            hints: Some(StenoHintMode::AfterDelay(Duration::from_secs(2))),
+            suggestions: true,
+            dictionary_paths: Vec::new(),
+        }
+    }
+}
+
+/// Ids of the dictionaries to choose from, along with their names
+pub const LANGUAGES: [(&str, &str); 3] = [("en", "english"), ("ar", "arabic"), ("he", "hebrew")];
+
+/// Text sizes to choose from, in rems
+pub const TEXT_SIZE_PRESETS: [f32; 3] = [1.5, 1.875, 2.5];
+/// Visible line counts to choose from for wrapped text
+pub const VISIBLE_LINE_PRESETS: [usize; 3] = [1, 3, 5];
+/// Letter spacings to choose from, in pixels
+pub const LETTER_SPACING_PRESETS: [f32; 3] = [0.0, 1.0, 3.0];
+/// Caret positions across the view to choose from for tape text
+pub const CARET_POSITION_PRESETS: [f32; 3] = [0.25, 0.5, 0.75];
+/// Seconds without a stroke to choose from before showing steno hints
+pub const STENO_HINT_DELAY_PRESETS: [u64; 3] = [1, 2, 5];
+
+/// Seconds for the timed test presets
+pub const TIME_PRESETS: [u64; 4] = [15, 30, 60, 120];
+/// Number of words for the word count test presets
+pub const WORD_PRESETS: [usize; 4] = [10, 25, 50, 100];
+/// Seconds which a custom timed test is shortened or lengthened by
+pub const TIME_STEP: u64 = 5;
+/// Number of words which a custom word count test is shortened or lengthened by
+pub const WORD_STEP: usize = 5;
+
+/// When a test ends
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TestLength {
+    /// After this many seconds
+    Time(u64),
+    /// Once this many words have been finished
+    Words(usize),
+}
+
+impl TestLength {
+    /// The next multiple of the step below this length, or the shortest length of 1
+    pub fn shorter(self) -> Self {
+        match self {
+            Self::Time(seconds) => Self::Time(((seconds - 1) / TIME_STEP * TIME_STEP).max(1)),
+            Self::Words(words) => Self::Words(((words - 1) / WORD_STEP * WORD_STEP).max(1)),
+        }
+    }
+
+    /// The next multiple of the step above this length
+    pub fn longer(self) -> Self {
+        match self {
+            Self::Time(seconds) => Self::Time((seconds / TIME_STEP + 1) * TIME_STEP),
+            Self::Words(words) => Self::Words((words / WORD_STEP + 1) * WORD_STEP),
+        }
+    }
+}
+
+/// Where the text to type comes from
+#[derive(Clone, PartialEq, Eq)]
+pub enum TextSource {
+    /// Random words from the language's dictionary
+    Dictionary,
+    /// Drills from the steno lesson with this id, in order
+    StenoLesson(String),
+}
+
+#[derive(Clone)]
+pub struct Settings {
+    /// Id of the dictionary to draw words from
+    pub language: &'static str,
+    pub text_source: TextSource,
+    /// Length of a test, which can be any value above zero rather than only one of the presets
+    pub test_length: TestLength,
+    /// Layout which the system is set to
+    pub keyboard_layout: &'static KeyboardLayout,
+    /// Layout to translate keys to, for learning a layout which the system isn't set to
+    pub emulated_layout: Option<KeyboardLayout>,
+    /// File which `emulated_layout` was read from, in the format of [`KeyboardLayout::parse`]
+    pub custom_layout_path: Option<PathBuf>,
+    /// Shows an on-screen keyboard with the next key and finger to type it with
+    pub keyboard_hints: bool,
+    pub steno: StenoSettings,
+    pub text_view: TextViewSettings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            language: "en",
+            text_source: TextSource::Dictionary,
+            test_length: TestLength::Time(30),
+            keyboard_layout: &QWERTY,
+            emulated_layout: None,
+            custom_layout_path: None,
+            keyboard_hints: false,
+            steno: StenoSettings::default(),
+            text_view: TextViewSettings::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// Layout which keys type characters from
+    pub fn typing_layout(&self) -> &KeyboardLayout {
+        self.emulated_layout
+            .as_ref()
+            .unwrap_or(self.keyboard_layout)
+    }
+}
+
+impl Global for Settings {}
+
+pub trait ActiveSettings {
+    fn settings(&self) -> &Settings;
+}
+
+impl ActiveSettings for App {
+    fn settings(&self) -> &Settings {
+        self.global()
+    }
+}
+
+impl<T> ActiveSettings for Context<'_, T> {
+    fn settings(&self) -> &Settings {
+        self.global()
+    }
+}
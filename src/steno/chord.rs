@@ -0,0 +1,176 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use super::Stroke;
+
+/// Keys of a keyboard used as a steno machine, each mapped to steno keys written as in Plover
+/// (`S-` and `-S` for either bank)
+#[derive(Clone, PartialEq, Debug)]
+pub struct StenoKeymap {
+    pub name: String,
+    keys: HashMap<String, Stroke>,
+}
+
+impl StenoKeymap {
+    /// Plover's default layout for QWERTY keyboards, with the steno keys on the letter keys and
+    /// the number bar on the number row
+    pub fn plover_qwerty() -> Self {
+        Self {
+            name: "Plover QWERTY".to_string(),
+            keys: PLOVER_QWERTY_KEYS
+                .iter()
+                .map(|&(key, steno)| (key.to_string(), Stroke::parse(steno).unwrap()))
+                .collect(),
+        }
+    }
+
+    /// Reads a keymap in the JSON format of Plover's keyboard machine, which maps each steno key
+    /// to a list of keys. The `no-op` and `arpeggiate` actions are ignored.
+    pub fn parse(name: &str, json: &str) -> Option<Self> {
+        let entries = serde_json::from_str::<HashMap<String, Vec<String>>>(json).ok()?;
+        let mut keys = HashMap::new();
+        for (steno, steno_keys) in entries {
+            if steno == "no-op" || steno == "arpeggiate" {
+                continue;
+            }
+            let stroke = Stroke::parse(&steno)?;
+            keys.extend(steno_keys.into_iter().map(|key| (key, stroke)));
+        }
+
+        (!keys.is_empty()).then(|| {
+            Self {
+                name: name.to_string(),
+                keys,
+            }
+        })
+    }
+
+    /// Reads a keymap from a file in the format of [`Self::parse`], named after the file
+    pub fn load(path: &Path) -> Option<Self> {
+        let name = path.file_stem()?.to_string_lossy();
+        Self::parse(&name, &std::fs::read_to_string(path).ok()?)
+    }
+
+    fn stroke(&self, key: &str) -> Option<Stroke> {
+        self.keys.get(key).copied()
+    }
+}
+
+const PLOVER_QWERTY_KEYS: [(&str, &str); 38] = [
+    ("1", "#"),
+    ("2", "#"),
+    ("3", "#"),
+    ("4", "#"),
+    ("5", "#"),
+    ("6", "#"),
+    ("7", "#"),
+    ("8", "#"),
+    ("9", "#"),
+    ("0", "#"),
+    ("-", "#"),
+    ("=", "#"),
+    ("q", "S-"),
+    ("a", "S-"),
+    ("w", "T-"),
+    ("s", "K-"),
+    ("e", "P-"),
+    ("d", "W-"),
+    ("r", "H-"),
+    ("f", "R-"),
+    ("c", "A-"),
+    ("v", "O-"),
+    ("t", "*"),
+    ("y", "*"),
+    ("g", "*"),
+    ("h", "*"),
+    ("n", "-E"),
+    ("m", "-U"),
+    ("u", "-F"),
+    ("j", "-R"),
+    ("i", "-P"),
+    ("k", "-B"),
+    ("o", "-L"),
+    ("l", "-G"),
+    ("p", "-T"),
+    (";", "-S"),
+    ("[", "-D"),
+    ("'", "-Z"),
+];
+
+/// Builds strokes from keys pressed together, finishing each one once every key is released
+#[derive(Default)]
+pub struct ChordBuilder {
+    held: HashSet<String>,
+    chord: Stroke,
+}
+
+impl ChordBuilder {
+    /// Adds a key to the chord, returning whether it is a steno key
+    pub fn key_down(&mut self, keymap: &StenoKeymap, key: &str) -> bool {
+        let Some(stroke) = keymap.stroke(key) else {
+            return false;
+        };
+
+        self.held.insert(key.to_string());
+        self.chord |= stroke;
+        true
+    }
+
+    /// Releases a key, returning the finished stroke once no keys are held
+    pub fn key_up(&mut self, key: &str) -> Option<Stroke> {
+        if !self.held.remove(key) || !self.held.is_empty() {
+            return None;
+        }
+
+        Some(std::mem::take(&mut self.chord))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chord_finishes_on_release() {
+        let mut chords = ChordBuilder::default();
+        let keymap = StenoKeymap::plover_qwerty();
+        assert!(chords.key_down(&keymap, "s"));
+        assert!(chords.key_down(&keymap, "c"));
+        assert_eq!(chords.key_up("s"), None);
+        assert!(chords.key_down(&keymap, "p"));
+        assert_eq!(chords.key_up("c"), None);
+        assert_eq!(chords.key_up("p"), Stroke::parse("KAT"));
+
+        assert!(chords.key_down(&keymap, "l"));
+        assert_eq!(chords.key_up("l"), Stroke::parse("-G"));
+    }
+
+    #[test]
+    fn ignores_other_keys() {
+        let mut chords = ChordBuilder::default();
+        let keymap = StenoKeymap::plover_qwerty();
+        assert!(!chords.key_down(&keymap, "z"));
+        assert_eq!(chords.key_up("z"), None);
+
+        assert!(chords.key_down(&keymap, "1"));
+        assert!(chords.key_down(&keymap, "q"));
+        assert_eq!(chords.key_up("z"), None);
+        assert_eq!(chords.key_up("1"), None);
+        assert_eq!(chords.key_up("q"), Stroke::parse("1"));
+    }
+
+    #[test]
+    fn parse_keymap() {
+        let keymap = StenoKeymap::parse(
+            "test",
+            r#"{"S-": ["a", "q"], "-Z": ["'"], "no-op": ["z"], "arpeggiate": ["space"]}"#,
+        )
+        .unwrap();
+        assert_eq!(keymap.stroke("q"), Stroke::parse("S"));
+        assert_eq!(keymap.stroke("'"), Stroke::parse("-Z"));
+        assert_eq!(keymap.stroke("z"), None);
+
+        assert!(StenoKeymap::parse("test", r#"{"X-": ["x"]}"#).is_none());
+        assert!(StenoKeymap::parse("test", "{}").is_none());
+    }
+}
@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use gpui::{App, Global};
+use rust_embed::Embed;
+
+use super::{format_outline, Stroke};
+
+#[derive(Embed)]
+#[folder = "assets/steno"]
+#[include = "*.json"]
+struct EmbeddedDictionaries;
+
+/// Translations of steno outlines, merged from Plover JSON dictionaries
+#[derive(Default)]
+pub struct StenoDictionary {
+    entries: HashMap<Vec<Stroke>, String>,
+    /// Outlines of each translation, shortest first
+    outlines: HashMap<String, Vec<Vec<Stroke>>>,
+    /// Number of strokes in the longest outline
+    longest_outline: usize,
+}
+
+impl Global for StenoDictionary {}
+
+impl StenoDictionary {
+    /// Loads the embedded dictionaries followed by the files at `paths`, with later dictionaries
+    /// overriding earlier ones
+    pub fn load(paths: &[PathBuf]) -> Self {
+        let mut dictionary = Self::default();
+        for file in EmbeddedDictionaries::iter() {
+            let data = EmbeddedDictionaries::get(&file).unwrap();
+            if !dictionary.add_json(&data.data) {
+                println!("Failed to load steno dictionary `{file}`");
+            }
+        }
+        for path in paths {
+            let loaded = std::fs::read(path).is_ok_and(|data| dictionary.add_json(&data));
+            if !loaded {
+                println!("Failed to load steno dictionary `{}`", path.display());
+            }
+        }
+        dictionary.index_outlines();
+
+        dictionary
+    }
+
+    /// A dictionary of just these entries
+    pub fn from_entries(entries: impl IntoIterator<Item = (Vec<Stroke>, String)>) -> Self {
+        let mut dictionary = Self::default();
+        for (outline, translation) in entries {
+            dictionary.longest_outline = dictionary.longest_outline.max(outline.len());
+            dictionary.entries.insert(outline, translation);
+        }
+        dictionary.index_outlines();
+        dictionary
+    }
+
+    #[cfg(test)]
+    pub fn from_json(json: &str) -> Self {
+        let mut dictionary = Self::default();
+        assert!(dictionary.add_json(json.as_bytes()));
+        dictionary.index_outlines();
+        dictionary
+    }
+
+    pub fn set_global(self, cx: &mut App) {
+        cx.set_global(self);
+    }
+
+    /// Adds the entries of a Plover JSON dictionary, which maps `/` separated strokes to their
+    /// translations. Entries with invalid strokes are skipped.
+    fn add_json(&mut self, json: &[u8]) -> bool {
+        let Ok(entries) = serde_json::from_slice::<HashMap<String, String>>(json) else {
+            return false;
+        };
+
+        for (outline, translation) in entries {
+            let Some(outline) = outline
+                .split('/')
+                .map(Stroke::parse)
+                .collect::<Option<Vec<_>>>()
+            else {
+                continue;
+            };
+            self.longest_outline = self.longest_outline.max(outline.len());
+            self.entries.insert(outline, translation);
+        }
+
+        true
+    }
+
+    /// Groups outlines by their translation, once every dictionary has been added
+    fn index_outlines(&mut self) {
+        self.outlines.clear();
+        for (outline, translation) in &self.entries {
+            self.outlines
+                .entry(translation.clone())
+                .or_default()
+                .push(outline.clone());
+        }
+        for outlines in self.outlines.values_mut() {
+            outlines.sort_by_cached_key(|outline| (outline.len(), format_outline(outline)));
+        }
+    }
+
+    pub fn lookup(&self, outline: &[Stroke]) -> Option<&str> {
+        self.entries.get(outline).map(String::as_str)
+    }
+
+    /// Outlines which translate to `translation`, shortest first
+    pub fn outlines(&self, translation: &str) -> Vec<&[Stroke]> {
+        self.outlines
+            .get(translation)
+            .map_or_else(Vec::new, |outlines| {
+                outlines.iter().map(Vec::as_slice).collect()
+            })
+    }
+
+    pub fn longest_outline(&self) -> usize {
+        self.longest_outline
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn embedded_outlines_are_valid() {
+        for file in EmbeddedDictionaries::iter() {
+            let data = EmbeddedDictionaries::get(&file).unwrap();
+            let entries = serde_json::from_slice::<HashMap<String, String>>(&data.data).unwrap();
+            for outline in entries.keys() {
+                for stroke in outline.split('/') {
+                    assert_eq!(
+                        Stroke::parse(stroke)
+                            .map(|stroke| stroke.to_string())
+                            .as_deref(),
+                        Some(stroke),
+                        "{file}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn multi_stroke_lookup() {
+        let dictionary =
+            StenoDictionary::from_json(r#"{"KAT": "cat", "TPHAO/TKPWUS": "gnus", "X": "invalid"}"#);
+        let outline = |steno: &str| {
+            steno
+                .split('/')
+                .map(|stroke| Stroke::parse(stroke).unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(dictionary.lookup(&outline("KAT")), Some("cat"));
+        assert_eq!(dictionary.lookup(&outline("TPHAO/TKPWUS")), Some("gnus"));
+        assert_eq!(dictionary.lookup(&outline("TPHAO")), None);
+        assert_eq!(dictionary.longest_outline(), 2);
+    }
+
+    #[test]
+    fn reverse_lookup() {
+        let dictionary = StenoDictionary::from_json(
+            r#"{"TKPWUS": "gnus", "TPHAO/TKPWUS": "gnus", "TPHUS": "gnus", "KAT": "cat"}"#,
+        );
+        let outlines = dictionary
+            .outlines("gnus")
+            .into_iter()
+            .map(format_outline)
+            .collect::<Vec<_>>();
+        assert_eq!(outlines, ["TKPWUS", "TPHUS", "TPHAO/TKPWUS"]);
+        assert!(dictionary.outlines("dog").is_empty());
+    }
+
+    #[test]
+    fn reverse_lookup_after_override() {
+        let mut dictionary = StenoDictionary::from_json(r#"{"KAT": "cat", "KAEUT": "cat"}"#);
+        assert!(dictionary.add_json(br#"{"KAT": "kat"}"#));
+        dictionary.index_outlines();
+
+        let outlines = |translation| {
+            dictionary
+                .outlines(translation)
+                .into_iter()
+                .map(format_outline)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(outlines("cat"), ["KAEUT"]);
+        assert_eq!(outlines("kat"), ["KAT"]);
+    }
+
+    #[test]
+    fn invalid_json() {
+        assert!(!StenoDictionary::default().add_json(b"[]"));
+    }
+}
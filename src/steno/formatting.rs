@@ -0,0 +1,262 @@
+use super::orthography::add_suffix;
+
+/// Text from a translation, along with how it joins to the text around it
+struct Piece {
+    text: String,
+    attach_left: bool,
+    attach_right: bool,
+    /// Whether the text is a suffix which changes the spelling of the word it attaches to
+    orthography: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Case {
+    Capitalize,
+    Lowercase,
+}
+
+enum Action {
+    Write(Piece),
+    SetCase(Case),
+}
+
+/// State carried from one translation to the next
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct Formatting {
+    /// Case to apply to the next text
+    case: Option<Case>,
+    /// Whether the next text joins to the previous text without a space
+    attach: bool,
+}
+
+/// Writes translations in Plover's format, returning the text written for each translation along
+/// with the formatting for the next one. Every word is followed by a space unless the next
+/// translation attaches to it, which can change the text of earlier translations.
+///
+/// Supported operators are `{^}` to attach, `{^text}`, `{text^}` and `{^text^}` for affixes,
+/// `{-|}` and `{>}` to capitalize or lowercase the next word, and punctuation such as `{.}` and
+/// `{,}`. Suffixes follow English orthography, so "make" + `{^ing}` is written as "making".
+pub fn format<'a>(
+    translations: impl IntoIterator<Item = &'a str>,
+    mut formatting: Formatting,
+) -> (Vec<String>, Formatting) {
+    let mut texts = Vec::new();
+    // Index of the translation which wrote the latest piece, which doesn't have its space yet
+    let mut latest = None;
+    let mut latest_attach_right = true;
+
+    for (idx, translation) in translations.into_iter().enumerate() {
+        texts.push(String::new());
+        for action in parse(translation) {
+            let mut piece = match action {
+                Action::Write(piece) => piece,
+                Action::SetCase(case) => {
+                    formatting.case = Some(case);
+                    continue;
+                }
+            };
+
+            if !piece.text.is_empty() {
+                if let Some(case) = formatting.case.take() {
+                    piece.text = apply_case(&piece.text, case);
+                }
+            }
+
+            let attach = piece.attach_left || formatting.attach;
+            if let Some(latest) = latest {
+                let previous: &mut String = &mut texts[latest];
+                if !attach && !latest_attach_right {
+                    previous.push(' ');
+                } else if attach && piece.orthography {
+                    let word_start = previous
+                        .rfind(|char: char| !char.is_alphabetic())
+                        .map_or(0, |idx| {
+                            idx + previous[idx..].chars().next().unwrap().len_utf8()
+                        });
+                    if word_start < previous.len() {
+                        let joined = add_suffix(&previous[word_start..], &piece.text);
+                        let common = previous[word_start..]
+                            .char_indices()
+                            .zip(joined.chars())
+                            .take_while(|&((_, a), b)| a == b)
+                            .last()
+                            .map_or(0, |((idx, char), _)| idx + char.len_utf8());
+                        piece.text = joined[common..].to_string();
+                        previous.truncate(word_start + common);
+                    }
+                }
+            }
+
+            texts[idx].push_str(&piece.text);
+            formatting.attach = piece.attach_right;
+            latest = Some(idx);
+            latest_attach_right = piece.attach_right;
+        }
+    }
+
+    // The latest word is followed by a space until something attaches to it
+    if let Some(latest) = latest.filter(|_| !latest_attach_right) {
+        texts[latest].push(' ');
+    }
+
+    (texts, formatting)
+}
+
+/// Splits a translation into literal text and operators in braces
+fn parse(translation: &str) -> Vec<Action> {
+    let mut actions = Vec::new();
+    let mut rest = translation;
+    while !rest.is_empty() {
+        let (literal, operator) = match rest.find('{') {
+            Some(open) => {
+                match rest[open..].find('}') {
+                    Some(close) => {
+                        let operator = &rest[open + 1..open + close];
+                        let literal = &rest[..open];
+                        rest = &rest[open + close + 1..];
+                        (literal, Some(operator))
+                    }
+                    None => (std::mem::take(&mut rest), None),
+                }
+            }
+            None => (std::mem::take(&mut rest), None),
+        };
+
+        let literal = literal.trim();
+        if !literal.is_empty() {
+            actions.push(Action::Write(Piece {
+                text: literal.to_string(),
+                attach_left: false,
+                attach_right: false,
+                orthography: false,
+            }));
+        }
+        if let Some(operator) = operator {
+            actions.extend(parse_operator(operator));
+        }
+    }
+
+    actions
+}
+
+fn parse_operator(operator: &str) -> Vec<Action> {
+    match operator {
+        "^" => {
+            vec![Action::Write(Piece {
+                text: String::new(),
+                attach_left: true,
+                attach_right: true,
+                orthography: false,
+            })]
+        }
+        "-|" => vec![Action::SetCase(Case::Capitalize)],
+        ">" => vec![Action::SetCase(Case::Lowercase)],
+        "." | "?" | "!" => {
+            vec![
+                Action::Write(Piece {
+                    text: operator.to_string(),
+                    attach_left: true,
+                    attach_right: false,
+                    orthography: false,
+                }),
+                Action::SetCase(Case::Capitalize),
+            ]
+        }
+        "," | ":" | ";" => {
+            vec![Action::Write(Piece {
+                text: operator.to_string(),
+                attach_left: true,
+                attach_right: false,
+                orthography: false,
+            })]
+        }
+        _ => {
+            let (attach_left, text) = match operator.strip_prefix('^') {
+                Some(text) => (true, text),
+                None => (false, operator),
+            };
+            let (attach_right, text) = match text.strip_suffix('^') {
+                Some(text) => (true, text),
+                None => (false, text),
+            };
+            if text.is_empty() && !(attach_left || attach_right) {
+                return Vec::new();
+            }
+
+            vec![Action::Write(Piece {
+                text: text.to_string(),
+                attach_left,
+                attach_right,
+                orthography: attach_left && text.starts_with(char::is_alphabetic),
+            })]
+        }
+    }
+}
+
+fn apply_case(text: &str, case: Case) -> String {
+    let mut chars = text.chars();
+    let Some(first) = chars.next() else {
+        return String::new();
+    };
+
+    match case {
+        Case::Capitalize => first.to_uppercase().chain(chars).collect(),
+        Case::Lowercase => first.to_lowercase().chain(chars).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format_all(translations: &[&str]) -> Vec<String> {
+        format(translations.iter().copied(), Formatting::default()).0
+    }
+
+    #[test]
+    fn spaces() {
+        assert_eq!(format_all(&["the", "cat"]), ["the ", "cat "]);
+        assert_eq!(format_all(&["a lot", "of"]), ["a lot ", "of "]);
+        assert_eq!(format_all(&[]), Vec::<String>::new());
+    }
+
+    #[test]
+    fn attach() {
+        assert_eq!(format_all(&["the", "{^}", "cat"]), ["the", "", "cat "]);
+        assert_eq!(format_all(&["the", "{^}"]), ["the", ""]);
+        assert_eq!(format_all(&["{re^}", "make"]), ["re", "make "]);
+        assert_eq!(
+            format_all(&["well", "{^-^}", "known"]),
+            ["well", "-", "known "]
+        );
+        assert_eq!(format_all(&["cat", "{^}{^s}"]), ["cat", "s "]);
+    }
+
+    #[test]
+    fn suffixes() {
+        assert_eq!(format_all(&["make", "{^ing}"]), ["mak", "ing "]);
+        assert_eq!(format_all(&["carry", "{^ed}"]), ["carr", "ied "]);
+        assert_eq!(format_all(&["work", "{^s}", "{^}"]), ["work", "s", ""]);
+        assert_eq!(format_all(&["stop", "{^ed}"]), ["stop", "ped "]);
+        assert_eq!(format_all(&["{^ing}"]), ["ing "]);
+    }
+
+    #[test]
+    fn case_and_punctuation() {
+        assert_eq!(
+            format_all(&["{-|}", "the", "cat", "{.}", "it"]),
+            ["", "The ", "cat", ". ", "It "]
+        );
+        assert_eq!(
+            format_all(&["yes", "{,}", "{>}", "Sir"]),
+            ["yes", ", ", "", "sir "]
+        );
+        assert_eq!(
+            format(["{-|}"], Formatting::default()).1,
+            Formatting {
+                case: Some(Case::Capitalize),
+                attach: false
+            }
+        );
+    }
+}
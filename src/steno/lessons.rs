@@ -0,0 +1,278 @@
+use std::collections::{HashMap, HashSet};
+
+use gpui::{App, Global};
+use rust_embed::Embed;
+
+use super::{format_outline, StenoDictionary, Stroke};
+use crate::typing_session::WordRecord;
+
+#[derive(Embed)]
+#[folder = "assets/lessons"]
+#[include = "*.txt"]
+struct EmbeddedLessons;
+
+/// An outline to practice along with the text it writes
+pub struct Drill {
+    pub outline: Vec<Stroke>,
+    pub translation: String,
+}
+
+pub struct StenoLesson {
+    /// Name of the lesson's file, which lessons are ordered by
+    pub id: String,
+    pub name: String,
+    pub drills: Vec<Drill>,
+}
+
+impl StenoLesson {
+    /// Parses a lesson from its name followed by a drill on each line. A drill is a `/` separated
+    /// outline followed by its translation, or just the outline for strokes which are written as
+    /// steno. Blank lines are ignored.
+    pub fn parse(id: &str, source: &str) -> Option<Self> {
+        let mut lines = source.lines().filter(|line| !line.trim().is_empty());
+        let name = lines.next()?.trim().to_string();
+        let drills = lines
+            .map(|line| {
+                let (outline, translation) = line
+                    .trim()
+                    .split_once(char::is_whitespace)
+                    .map_or((line.trim(), None), |(outline, translation)| {
+                        (outline, Some(translation.trim()))
+                    });
+                let outline = outline
+                    .split('/')
+                    .map(Stroke::parse)
+                    .collect::<Option<Vec<_>>>()?;
+                let translation =
+                    translation.map_or_else(|| format_outline(&outline), str::to_string);
+                Some(Drill {
+                    outline,
+                    translation,
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        (!drills.is_empty()).then(|| {
+            Self {
+                id: id.to_string(),
+                name,
+                drills,
+            }
+        })
+    }
+
+    /// Outline which the lesson uses for a translation
+    pub fn outline(&self, translation: &str) -> Option<&[Stroke]> {
+        self.drills
+            .iter()
+            .find(|drill| drill.translation == translation)
+            .map(|drill| drill.outline.as_slice())
+    }
+
+    /// `word_count` words from the translations of the drills from `start`, wrapping around to
+    /// the first drill. The last drill is cut short if it has more words than are left. Returns
+    /// the text along with the number of drills it was taken from.
+    pub fn text(&self, start: usize, word_count: usize) -> (String, usize) {
+        let mut words = Vec::new();
+        let mut drills = 0;
+        for drill in self.drills.iter().cycle().skip(start % self.drills.len()) {
+            if words.len() >= word_count {
+                break;
+            }
+            let remaining = word_count - words.len();
+            words.extend(drill.translation.split_whitespace().take(remaining));
+            drills += 1;
+        }
+
+        (words.join(" "), drills)
+    }
+
+    /// Drills which are written as steno, each translating to itself so that they aren't counted
+    /// as untranslated strokes
+    pub fn steno_drills(&self) -> StenoDictionary {
+        StenoDictionary::from_entries(
+            self.drills
+                .iter()
+                .filter(|drill| drill.translation == format_outline(&drill.outline))
+                .map(|drill| (drill.outline.clone(), drill.translation.clone())),
+        )
+    }
+}
+
+/// Steno lessons, along with the words of each which have been typed correctly across sessions
+#[derive(Default)]
+pub struct StenoLessons {
+    lessons: Vec<StenoLesson>,
+    typed_words: HashMap<String, HashSet<String>>,
+}
+
+impl Global for StenoLessons {}
+
+impl StenoLessons {
+    pub fn load() -> Self {
+        let mut lessons = EmbeddedLessons::iter()
+            .filter_map(|file| {
+                let data = EmbeddedLessons::get(&file).unwrap();
+                let id = file.strip_suffix(".txt").unwrap_or(&file);
+                let lesson = std::str::from_utf8(&data.data)
+                    .ok()
+                    .and_then(|source| StenoLesson::parse(id, source));
+                if lesson.is_none() {
+                    println!("Failed to load steno lesson `{file}`");
+                }
+                lesson
+            })
+            .collect::<Vec<_>>();
+        lessons.sort_by(|a, b| a.id.cmp(&b.id));
+
+        Self {
+            lessons,
+            typed_words: HashMap::new(),
+        }
+    }
+
+    pub fn set_global(self, cx: &mut App) {
+        cx.set_global(self);
+    }
+
+    /// Lessons in the order of their ids
+    pub fn lessons(&self) -> &[StenoLesson] {
+        &self.lessons
+    }
+
+    pub fn lesson(&self, id: &str) -> Option<&StenoLesson> {
+        self.lessons.iter().find(|lesson| lesson.id == id)
+    }
+
+    /// Adds the correctly typed words of a session to a lesson's progress
+    pub fn record(&mut self, id: &str, words: &[WordRecord]) {
+        let typed_words = self.typed_words.entry(id.to_string()).or_default();
+        typed_words.extend(
+            words
+                .iter()
+                .filter(|word| word.typed == word.target)
+                .map(|word| word.target.clone()),
+        );
+    }
+
+    /// Number of drills in a lesson whose words have all been typed correctly
+    pub fn completed_drills(&self, lesson: &StenoLesson) -> usize {
+        let Some(typed_words) = self.typed_words.get(&lesson.id) else {
+            return 0;
+        };
+
+        lesson
+            .drills
+            .iter()
+            .filter(|drill| {
+                drill
+                    .translation
+                    .split_whitespace()
+                    .all(|word| typed_words.contains(word))
+            })
+            .count()
+    }
+
+    /// Number of lessons with every drill completed
+    pub fn completed_lessons(&self) -> usize {
+        self.lessons
+            .iter()
+            .filter(|lesson| self.completed_drills(lesson) == lesson.drills.len())
+            .count()
+    }
+
+    pub fn lesson_count(&self) -> usize {
+        self.lessons.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::steno::Translator;
+    use crate::typing_session::{Input, TypingSession};
+
+    #[test]
+    fn parse() {
+        let lesson =
+            StenoLesson::parse("test", "Test\n\nKAT cat\nTPHAO/TKPWUS gnus\n-FR\n").unwrap();
+        assert_eq!(lesson.name, "Test");
+        assert_eq!(
+            lesson
+                .drills
+                .iter()
+                .map(|drill| (format_outline(&drill.outline), drill.translation.as_str()))
+                .collect::<Vec<_>>(),
+            [
+                ("KAT".to_string(), "cat"),
+                ("TPHAO/TKPWUS".to_string(), "gnus"),
+                ("-FR".to_string(), "-FR"),
+            ]
+        );
+        assert_eq!(lesson.text(2, 3), ("-FR cat gnus".to_string(), 3));
+        assert_eq!(lesson.text(3, 4), ("cat gnus -FR cat".to_string(), 4));
+
+        let lesson = StenoLesson::parse("test", "Test\nKAT cat\nA/TKOG a dog\n").unwrap();
+        assert_eq!(lesson.text(0, 2), ("cat a".to_string(), 2));
+        assert_eq!(lesson.text(1, 2), ("a dog".to_string(), 1));
+
+        assert!(StenoLesson::parse("test", "Test\n").is_none());
+        assert!(StenoLesson::parse("test", "Test\nKAT cat\nKQ invalid\n").is_none());
+    }
+
+    #[test]
+    fn embedded_lessons_translate() {
+        let dictionary = StenoDictionary::load(&[]);
+        let lessons = StenoLessons::load();
+        assert_eq!(lessons.lesson_count(), EmbeddedLessons::iter().count());
+
+        for lesson in &lessons.lessons {
+            let mut translator = Translator::new(lesson.steno_drills());
+            let mut outputs = Vec::new();
+            for stroke in lesson.drills.iter().flat_map(|drill| &drill.outline) {
+                let output = translator.translate(*stroke, &dictionary);
+                outputs.truncate(outputs.len() - output.undo);
+                outputs.extend(output.texts);
+            }
+            let word_count = lesson
+                .drills
+                .iter()
+                .map(|drill| drill.translation.split_whitespace().count())
+                .sum();
+            assert_eq!(
+                outputs.concat(),
+                format!("{} ", lesson.text(0, word_count).0),
+                "lesson `{}`",
+                lesson.id
+            );
+            assert_eq!(
+                translator.stats().accuracy(),
+                100.0,
+                "lesson `{}`",
+                lesson.id
+            );
+        }
+    }
+
+    #[test]
+    fn progress() {
+        let mut lessons = StenoLessons::default();
+        lessons
+            .lessons
+            .push(StenoLesson::parse("test", "Test\nKAT cat\nA/TKOG a dog\n").unwrap());
+
+        let mut session = TypingSession::new("cat a dog".to_string());
+        for text in ["cat ", "a ", "dig "] {
+            session.handle_input(Input::Text(text.to_string()));
+        }
+        lessons.record("test", session.words());
+        assert_eq!(lessons.completed_drills(&lessons.lessons[0]), 1);
+        assert_eq!(lessons.completed_lessons(), 0);
+
+        let mut session = TypingSession::new("a dog cat".to_string());
+        session.handle_input(Input::Text("a dog ".to_string()));
+        lessons.record("test", session.words());
+        assert_eq!(lessons.completed_drills(&lessons.lessons[0]), 2);
+        assert_eq!(lessons.completed_lessons(), 1);
+    }
+}
@@ -0,0 +1,19 @@
+mod chord;
+mod dictionary;
+mod formatting;
+mod lessons;
+mod orthography;
+mod protocol;
+#[cfg(not(target_family = "wasm"))]
+mod serial;
+mod stroke;
+mod translator;
+
+pub use chord::{ChordBuilder, StenoKeymap};
+pub use dictionary::StenoDictionary;
+pub use lessons::StenoLessons;
+pub use protocol::StenoProtocol;
+#[cfg(not(target_family = "wasm"))]
+pub use serial::SerialMachine;
+pub use stroke::{format_outline, Stroke};
+pub use translator::{StenoStats, TapeEntry, TapeTranslation, Translator};
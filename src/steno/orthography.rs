@@ -0,0 +1,124 @@
+const VOWELS: &str = "aeiou";
+const CONSONANTS: &str = "bcdfghjklmnpqrstvwxz";
+
+/// Joins a suffix onto a word using a subset of Plover's English orthography rules, such as
+/// "make" + "ing" = "making" and "carry" + "s" = "carries"
+pub fn add_suffix(word: &str, suffix: &str) -> String {
+    // Rules only look at ASCII letters, so byte lengths match the original word
+    let lower = word.to_ascii_lowercase();
+    let stem = |len: usize| &word[..word.len() - len];
+    let first = suffix.chars().next().map(|char| char.to_ascii_lowercase());
+    let starts_with_vowel = first.is_some_and(|char| "aeiouy".contains(char));
+
+    // artistic + ly = artistically
+    if suffix == "ly" && ends_after(&lower, VOWELS, "c") {
+        return format!("{word}ally");
+    }
+    // humble + ly = humbly
+    if suffix == "ly" && ends_after(&lower, "aeioubmnp", "le") {
+        return format!("{}ly", stem(2));
+    }
+    // establish + s = establishes, speech + s = speeches
+    if suffix == "s"
+        && (["s", "sh", "x", "z", "zh"]
+            .iter()
+            .any(|ending| lower.ends_with(ending))
+            || ["oa", "ea", "i", "ee", "oo", "au", "ou", "l", "n", "r", "t"]
+                .iter()
+                .any(|before| lower.ends_with(&format!("{before}ch"))))
+    {
+        return format!("{word}es");
+    }
+    if lower.len() > 2 && ends_after(&lower, CONSONANTS, "y") {
+        // cherry + s = cherries
+        if suffix == "s" {
+            return format!("{}ies", stem(1));
+        }
+        // carry + ed = carried, but carry + ing = carrying
+        if first.is_some_and(|char| char.is_ascii_alphabetic() && char != 'i') {
+            return format!("{}i{suffix}", stem(1));
+        }
+    }
+    // die + ing = dying
+    if suffix == "ing" && lower.len() > 2 && lower.ends_with("ie") {
+        return format!("{}ying", stem(2));
+    }
+    // write + en = written
+    if suffix == "en" && lower.len() > 2 && lower.ends_with("te") {
+        return format!("{}tten", stem(2));
+    }
+    // free + ed = freed
+    if first == Some('e') && lower.len() > 2 && lower.ends_with("ee") {
+        return format!("{word}{}", &suffix[1..]);
+    }
+    // make + ing = making, continue + ing = continuing
+    if starts_with_vowel && lower.len() > 2 && ends_after(&lower, "bcdfghjklmnpqrstuvwxz", "e") {
+        return format!("{}{suffix}", stem(1));
+    }
+    // stop + ed = stopped
+    if starts_with_vowel && doubles_final_consonant(&lower) {
+        let last = &word[word.len() - 1..];
+        return format!("{word}{last}{suffix}");
+    }
+
+    format!("{word}{suffix}")
+}
+
+/// Whether `word` ends with `ending` directly after one of the `preceding` characters
+fn ends_after(word: &str, preceding: &str, ending: &str) -> bool {
+    word.strip_suffix(ending)
+        .and_then(|stem| stem.chars().last())
+        .is_some_and(|char| preceding.contains(char))
+}
+
+/// Whether a word of one syllable ends with a single vowel and a consonant. Longer words are left
+/// alone, since whether they double depends on which syllable is stressed ("visit" + "ed").
+fn doubles_final_consonant(word: &str) -> bool {
+    let chars = word.chars().collect::<Vec<_>>();
+    // The "u" in "qu" acts as a consonant
+    let is_vowel = |idx: usize| {
+        VOWELS.contains(chars[idx]) && !(chars[idx] == 'u' && idx > 0 && chars[idx - 1] == 'q')
+    };
+    let [.., _, _, last] = chars[..] else {
+        return false;
+    };
+
+    let vowels = (0..chars.len()).filter(|&idx| is_vowel(idx)).count();
+    vowels == 1 && is_vowel(chars.len() - 2) && "bcdfgklmnprtvz".contains(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suffixes() {
+        for (word, suffix, joined) in [
+            ("make", "ing", "making"),
+            ("Make", "ing", "Making"),
+            ("continue", "ing", "continuing"),
+            ("see", "ing", "seeing"),
+            ("free", "ed", "freed"),
+            ("make", "s", "makes"),
+            ("work", "s", "works"),
+            ("play", "s", "plays"),
+            ("church", "s", "churches"),
+            ("stomach", "s", "stomachs"),
+            ("box", "s", "boxes"),
+            ("carry", "s", "carries"),
+            ("carry", "ed", "carried"),
+            ("carry", "ing", "carrying"),
+            ("die", "ing", "dying"),
+            ("write", "en", "written"),
+            ("artistic", "ly", "artistically"),
+            ("humble", "ly", "humbly"),
+            ("stop", "ed", "stopped"),
+            ("quit", "ing", "quitting"),
+            ("visit", "ed", "visited"),
+            ("look", "ing", "looking"),
+            ("fix", "ed", "fixed"),
+        ] {
+            assert_eq!(add_suffix(word, suffix), joined, "{word} + {suffix}");
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use std::mem;
+
+use super::Stroke;
+
+/// Keys of each bit in a Gemini PR packet, from the most significant of the first byte's seven
+/// data bits. Function, power and reserved keys are left empty.
+const GEMINI_PR_KEYS: [&str; 42] = [
+    "", "#", "#", "#", "#", "#", "#", "S-", "S-", "T-", "K-", "P-", "W-", "H-", "R-", "A-", "O-",
+    "*", "*", "", "", "", "*", "*", "-E", "-U", "-F", "-R", "-P", "-B", "-L", "-G", "-T", "-S",
+    "-D", "#", "#", "#", "#", "#", "#", "-Z",
+];
+const GEMINI_PR_PACKET_LEN: usize = 6;
+
+/// Keys of each bit in a TX Bolt byte, from the least significant bit of the first key set
+const TX_BOLT_KEYS: [&str; 23] = [
+    "S-", "T-", "K-", "P-", "W-", "H-", "R-", "A-", "O-", "*", "-E", "-U", "-F", "-R", "-P", "-B",
+    "-L", "-G", "-T", "-S", "-D", "-Z", "#",
+];
+const TX_BOLT_LAST_KEY_SET: u8 = 3;
+
+/// Serial protocols spoken by steno machines
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StenoProtocol {
+    /// Six byte packets, each starting with the only byte which has its high bit set
+    GeminiPr,
+    /// A byte for each of the four sets of keys which are pressed, in order
+    TxBolt,
+}
+
+/// Decodes strokes from the bytes sent by a steno machine
+pub struct StenoDecoder {
+    protocol: StenoProtocol,
+    packet: Vec<u8>,
+    stroke: Stroke,
+    /// TX Bolt key set of the previous byte in the stroke
+    last_key_set: Option<u8>,
+}
+
+impl StenoDecoder {
+    pub fn new(protocol: StenoProtocol) -> Self {
+        Self {
+            protocol,
+            packet: Vec::with_capacity(GEMINI_PR_PACKET_LEN),
+            stroke: Stroke::default(),
+            last_key_set: None,
+        }
+    }
+
+    /// Decodes received bytes, returning the strokes which they finish
+    pub fn decode(&mut self, bytes: &[u8]) -> Vec<Stroke> {
+        let mut strokes = Vec::new();
+        for &byte in bytes {
+            let stroke = match self.protocol {
+                StenoProtocol::GeminiPr => self.decode_gemini_pr(byte),
+                StenoProtocol::TxBolt => self.decode_tx_bolt(byte),
+            };
+            strokes.extend(stroke);
+        }
+
+        strokes
+    }
+
+    /// Finishes a partially received stroke once the machine stops sending. TX Bolt machines only
+    /// send the key sets which are pressed, so a stroke can't always be told apart from the start
+    /// of the next one until then.
+    pub fn finish(&mut self) -> Option<Stroke> {
+        self.packet.clear();
+        self.last_key_set = None;
+        let stroke = mem::take(&mut self.stroke);
+        (stroke != Stroke::default()).then_some(stroke)
+    }
+
+    fn decode_gemini_pr(&mut self, byte: u8) -> Option<Stroke> {
+        // Resynchronize on the first byte of a packet
+        if byte & 0x80 != 0 {
+            self.packet.clear();
+        } else if self.packet.is_empty() {
+            return None;
+        }
+
+        self.packet.push(byte);
+        if self.packet.len() < GEMINI_PR_PACKET_LEN {
+            return None;
+        }
+
+        let stroke = self
+            .packet
+            .drain(..)
+            .enumerate()
+            .flat_map(|(byte_idx, byte)| {
+                (0..7)
+                    .filter(move |bit| byte & (0x40 >> bit) != 0)
+                    .map(move |bit| GEMINI_PR_KEYS[byte_idx * 7 + bit])
+            })
+            .filter_map(Stroke::parse)
+            .fold(Stroke::default(), |stroke, key| stroke | key);
+        (stroke != Stroke::default()).then_some(stroke)
+    }
+
+    fn decode_tx_bolt(&mut self, byte: u8) -> Option<Stroke> {
+        let key_set = byte >> 6;
+
+        // Key sets are sent in order, so going back to an earlier set starts a new stroke
+        let mut finished = None;
+        if byte == 0 || self.last_key_set.is_some_and(|last| key_set <= last) {
+            finished = self.finish();
+        }
+        if byte == 0 {
+            return finished;
+        }
+
+        self.last_key_set = Some(key_set);
+        for bit in 0..6 {
+            if byte & (1 << bit) != 0 {
+                if let Some(key) = TX_BOLT_KEYS
+                    .get(key_set as usize * 6 + bit)
+                    .and_then(|key| Stroke::parse(key))
+                {
+                    self.stroke |= key;
+                }
+            }
+        }
+
+        // Nothing can follow the last key set, and the previous stroke can't be unfinished since it
+        // would have been finished by this byte's key set being after it
+        if key_set == TX_BOLT_LAST_KEY_SET {
+            return self.finish();
+        }
+        finished
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn steno(strokes: Vec<Stroke>) -> Vec<String> {
+        strokes.iter().map(Stroke::to_string).collect()
+    }
+
+    #[test]
+    fn gemini_pr() {
+        let mut decoder = StenoDecoder::new(StenoProtocol::GeminiPr);
+        // KAT, then every key except the number bar
+        let fixture = [
+            0x80, 0x08, 0x20, 0x00, 0x04, 0x00, //
+            0x80, 0x7f, 0x7c, 0x3f, 0x7f, 0x01,
+        ];
+        assert_eq!(
+            steno(decoder.decode(&fixture)),
+            ["KAT", "STKPWHRAO*EUFRPBLGTSDZ"]
+        );
+    }
+
+    #[test]
+    fn gemini_pr_resynchronizes() {
+        let mut decoder = StenoDecoder::new(StenoProtocol::GeminiPr);
+        // A stray data byte, a truncated packet and then a full packet split across reads
+        assert!(decoder.decode(&[0x08, 0x80, 0x00, 0x08]).is_empty());
+        assert!(decoder.decode(&[0x80, 0x08, 0x20]).is_empty());
+        assert_eq!(steno(decoder.decode(&[0x00, 0x04, 0x00])), ["KAT"]);
+    }
+
+    #[test]
+    fn tx_bolt() {
+        let mut decoder = StenoDecoder::new(StenoProtocol::TxBolt);
+        // KAT ending with the last key set, then -G and SKP which are finished by the next stroke
+        // or once the machine stops sending
+        let fixture = [0x04, 0x42, 0xc1, 0xa0, 0x0d];
+        assert_eq!(steno(decoder.decode(&fixture)), ["KAT", "-G"]);
+        assert_eq!(
+            decoder.finish().map(|stroke| stroke.to_string()),
+            Some("SKP".to_string())
+        );
+        assert_eq!(decoder.finish(), None);
+    }
+
+    #[test]
+    fn tx_bolt_zero_byte_finishes_stroke() {
+        let mut decoder = StenoDecoder::new(StenoProtocol::TxBolt);
+        assert_eq!(steno(decoder.decode(&[0x01, 0x00, 0x00])), ["S"]);
+    }
+}
@@ -0,0 +1,87 @@
+use std::io::{self, Read};
+use std::time::Duration;
+
+use serialport::SerialPort;
+
+use super::protocol::{StenoDecoder, StenoProtocol};
+use super::Stroke;
+
+const BAUD_RATE: u32 = 9600;
+/// How long a machine can go without sending before a partially received stroke is finished
+const READ_TIMEOUT: Duration = Duration::from_millis(50);
+
+/// A steno machine connected through a serial port
+pub struct SerialMachine<P> {
+    port: P,
+    decoder: StenoDecoder,
+}
+
+impl SerialMachine<Box<dyn SerialPort>> {
+    pub fn open(path: &str, protocol: StenoProtocol) -> serialport::Result<Self> {
+        let port = serialport::new(path, BAUD_RATE)
+            .timeout(READ_TIMEOUT)
+            .open()?;
+        Ok(Self::new(port, protocol))
+    }
+
+    /// Names of the serial ports which a machine could be connected through
+    pub fn port_names() -> Vec<String> {
+        serialport::available_ports()
+            .map(|ports| ports.into_iter().map(|port| port.port_name).collect())
+            .unwrap_or_default()
+    }
+}
+
+impl<P: Read> SerialMachine<P> {
+    pub fn new(port: P, protocol: StenoProtocol) -> Self {
+        Self {
+            port,
+            decoder: StenoDecoder::new(protocol),
+        }
+    }
+
+    /// Blocks until bytes are received or the read times out, returning the strokes which were
+    /// finished
+    pub fn read_strokes(&mut self) -> io::Result<Vec<Stroke>> {
+        let mut buffer = [0; 64];
+        match self.port.read(&mut buffer) {
+            Ok(0) => Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(len) => Ok(self.decoder.decode(&buffer[..len])),
+            Err(err) if err.kind() == io::ErrorKind::TimedOut => {
+                Ok(self.decoder.finish().into_iter().collect())
+            }
+            Err(err) => Err(err),
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::io::Write;
+
+    use serialport::TTYPort;
+
+    use super::*;
+
+    #[test]
+    fn reads_from_pseudo_terminal() {
+        let (mut machine_end, mut app_end) = TTYPort::pair().unwrap();
+        app_end.set_timeout(READ_TIMEOUT).unwrap();
+        let mut machine = SerialMachine::new(app_end, StenoProtocol::TxBolt);
+
+        // KAT, then -G which is only finished once the machine stops sending
+        machine_end.write_all(&[0x04, 0x42, 0xc1, 0xa0]).unwrap();
+        let mut strokes = Vec::new();
+        for _ in 0..20 {
+            strokes.extend(machine.read_strokes().unwrap());
+            if strokes.len() == 2 {
+                break;
+            }
+        }
+
+        assert_eq!(
+            strokes.iter().map(Stroke::to_string).collect::<Vec<_>>(),
+            ["KAT", "-G"]
+        );
+    }
+}
@@ -0,0 +1,204 @@
+use std::fmt;
+use std::ops::{BitOr, BitOrAssign};
+
+/// Number of keys on a steno machine
+const NUM_KEYS: usize = 23;
+
+/// Letter of each key in steno order, starting with the number bar
+const KEY_LETTERS: [char; NUM_KEYS] = [
+    '#', 'S', 'T', 'K', 'P', 'W', 'H', 'R', 'A', 'O', '*', 'E', 'U', 'F', 'R', 'P', 'B', 'L', 'G',
+    'T', 'S', 'D', 'Z',
+];
+
+/// Digit written for each key when the number bar is held
+const KEY_DIGITS: [Option<char>; NUM_KEYS] = [
+    None,
+    Some('1'),
+    Some('2'),
+    None,
+    Some('3'),
+    None,
+    Some('4'),
+    None,
+    Some('5'),
+    Some('0'),
+    None,
+    None,
+    None,
+    Some('6'),
+    None,
+    Some('7'),
+    None,
+    Some('8'),
+    None,
+    Some('9'),
+    None,
+    None,
+    None,
+];
+
+const NUMBER_KEY: usize = 0;
+/// Vowels and `*`, which separate the left and right banks
+const MIDDLE_KEYS: std::ops::Range<usize> = 8..13;
+const FIRST_RIGHT_KEY: usize = 11;
+
+/// A set of steno keys pressed together
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, Debug)]
+pub struct Stroke(u32);
+
+impl Stroke {
+    fn contains(self, key: usize) -> bool {
+        self.0 & (1 << key) != 0
+    }
+
+    fn keys(self) -> impl Iterator<Item = usize> {
+        (0..NUM_KEYS).filter(move |&key| self.contains(key))
+    }
+
+    /// Reads a stroke written in steno order, as in Plover's dictionaries. Right bank keys follow
+    /// a vowel, a `*` or a `-`, and digits stand for their key with the number bar.
+    pub fn parse(steno: &str) -> Option<Self> {
+        let mut stroke = 0;
+        let mut next_key = 0;
+
+        for char in steno.chars() {
+            if char == '-' {
+                if next_key > FIRST_RIGHT_KEY {
+                    return None;
+                }
+                next_key = FIRST_RIGHT_KEY;
+                continue;
+            }
+
+            let key = (next_key..NUM_KEYS)
+                .find(|&key| KEY_LETTERS[key] == char || KEY_DIGITS[key] == Some(char))?;
+            if KEY_DIGITS[key] == Some(char) {
+                stroke |= 1 << NUMBER_KEY;
+            }
+            stroke |= 1 << key;
+            next_key = key + 1;
+        }
+
+        (stroke != 0).then_some(Self(stroke))
+    }
+
+    /// Every key in steno order, with the keys which aren't pressed left blank so that strokes
+    /// line up in columns
+    pub fn paper_tape(self) -> String {
+        (0..NUM_KEYS)
+            .map(|key| {
+                if self.contains(key) {
+                    KEY_LETTERS[key]
+                } else {
+                    ' '
+                }
+            })
+            .collect()
+    }
+}
+
+/// Writes the strokes of an outline separated by `/`, as in Plover's dictionaries
+pub fn format_outline(outline: &[Stroke]) -> String {
+    outline
+        .iter()
+        .map(Stroke::to_string)
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+impl BitOr for Stroke {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for Stroke {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+/// Writes the stroke in steno order, as Plover does
+impl fmt::Display for Stroke {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let numbers = self.contains(NUMBER_KEY) && self.keys().any(|key| KEY_DIGITS[key].is_some());
+        let mut hyphen_needed = !self.keys().any(|key| MIDDLE_KEYS.contains(&key));
+
+        for key in self.keys() {
+            if key == NUMBER_KEY && numbers {
+                continue;
+            }
+            if key >= FIRST_RIGHT_KEY && hyphen_needed {
+                write!(f, "-")?;
+                hyphen_needed = false;
+            }
+
+            let digit = KEY_DIGITS[key].filter(|_| numbers);
+            write!(f, "{}", digit.unwrap_or(KEY_LETTERS[key]))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_display() {
+        for steno in [
+            "KAT",
+            "TPHAO",
+            "-G",
+            "-D",
+            "S-S",
+            "*",
+            "STKPWHRAO*EUFRPBLGTSDZ",
+            "#",
+            "1-9",
+            "12",
+            "50",
+            "KWR-PB",
+        ] {
+            assert_eq!(Stroke::parse(steno).unwrap().to_string(), steno);
+        }
+    }
+
+    #[test]
+    fn parse_sides() {
+        assert_ne!(Stroke::parse("T"), Stroke::parse("-T"));
+        assert_eq!(
+            Stroke::parse("TAT"),
+            Some(
+                Stroke::parse("T-").unwrap()
+                    | Stroke::parse("A").unwrap()
+                    | Stroke::parse("-T").unwrap()
+            )
+        );
+        assert_eq!(Stroke::parse("KT"), Stroke::parse("K-T"));
+        assert_eq!(Stroke::parse("1"), Stroke::parse("#S"));
+    }
+
+    #[test]
+    fn paper_tape() {
+        assert_eq!(
+            Stroke::parse("KAT").unwrap().paper_tape(),
+            "   K    A          T   "
+        );
+        assert_eq!(
+            Stroke::parse("1-9").unwrap().paper_tape(),
+            "#S                 T   "
+        );
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert_eq!(Stroke::parse(""), None);
+        assert_eq!(Stroke::parse("AA"), None);
+        assert_eq!(Stroke::parse("X"), None);
+        assert_eq!(Stroke::parse("-G-"), None);
+    }
+}
@@ -0,0 +1,439 @@
+use std::time::Duration;
+
+use super::formatting::{format, Formatting};
+use super::{StenoDictionary, Stroke};
+
+/// Translations kept for strokes to combine with, and strokes kept on the paper tape
+const HISTORY_LEN: usize = 100;
+
+/// Strokes translated together
+struct Translation {
+    strokes: Vec<Stroke>,
+    /// The dictionary entry for the strokes, or the strokes themselves if they weren't found
+    text: String,
+    translated: bool,
+}
+
+/// What a stroke on the paper tape did
+#[derive(Clone, Debug, PartialEq)]
+pub enum TapeTranslation {
+    /// The stroke finished this translation
+    Translated(String),
+    /// The stroke wasn't in the dictionary, so was written as steno
+    Untranslated,
+    /// The stroke removed the previous translation
+    Undo,
+}
+
+/// A stroke as shown on the paper tape
+#[derive(Clone, Debug, PartialEq)]
+pub struct TapeEntry {
+    pub stroke: Stroke,
+    pub translation: TapeTranslation,
+    /// A shorter outline for the translation than the one which was stroked
+    pub suggestion: Option<Vec<Stroke>>,
+}
+
+/// Changes to the written text from a stroke
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TranslatorOutput {
+    /// Number of previously written texts to remove before writing `texts`
+    pub undo: usize,
+    /// Texts to write separately, so that each can be undone by a later stroke
+    pub texts: Vec<String>,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct StenoStats {
+    pub strokes: usize,
+    /// Strokes which removed the previous translation
+    pub undos: usize,
+    /// Strokes which aren't part of any outline in the dictionary
+    pub untranslated: usize,
+    /// Translations which didn't match the text
+    pub misstrokes: usize,
+}
+
+impl StenoStats {
+    pub fn strokes_per_minute(&self, elapsed: Duration) -> f32 {
+        if elapsed.is_zero() {
+            return 0.0;
+        }
+
+        self.strokes as f32 / elapsed.as_secs_f32() * 60.0
+    }
+
+    /// Percentage of strokes which weren't misstrokes, untranslated or undos
+    pub fn accuracy(&self) -> f32 {
+        if self.strokes == 0 {
+            return 100.0;
+        }
+
+        let wrong = (self.misstrokes + self.untranslated + self.undos).min(self.strokes);
+        (self.strokes - wrong) as f32 / self.strokes as f32 * 100.0
+    }
+}
+
+/// Turns strokes into text, preferring the longest outline in the dictionary which ends with the
+/// latest stroke. This can replace the translations of previous strokes, and the asterisk stroke
+/// undoes the latest translation.
+#[derive(Default)]
+pub struct Translator {
+    translations: Vec<Translation>,
+    /// Text written for each translation
+    written: Vec<String>,
+    /// Formatting from translations which have left the history
+    formatting: Formatting,
+    tape: Vec<TapeEntry>,
+    stats: StenoStats,
+    /// Entries for the lesson being typed, which take priority over the main dictionary
+    lesson_dictionary: StenoDictionary,
+}
+
+impl Translator {
+    pub fn new(lesson_dictionary: StenoDictionary) -> Self {
+        Self {
+            lesson_dictionary,
+            ..Self::default()
+        }
+    }
+
+    /// Recent strokes, oldest first
+    pub fn tape(&self) -> &[TapeEntry] {
+        &self.tape
+    }
+
+    pub fn stats(&self) -> StenoStats {
+        self.stats
+    }
+
+    /// Counts the latest translation as a misstroke. Strokes which were untranslated or undos are
+    /// already counted as such.
+    pub fn record_misstroke(&mut self) {
+        if let Some(TapeEntry {
+            translation: TapeTranslation::Translated(_),
+            ..
+        }) = self.tape.last()
+        {
+            self.stats.misstrokes += 1;
+        }
+    }
+
+    pub fn translate(&mut self, stroke: Stroke, dictionary: &StenoDictionary) -> TranslatorOutput {
+        self.stats.strokes += 1;
+
+        if Some(stroke) == Stroke::parse("*") {
+            if let Some(translation) = self.translations.pop() {
+                self.stats.undos += 1;
+                // The stroke is now just counted as undone
+                if !translation.translated {
+                    self.stats.untranslated -= 1;
+                }
+            }
+            self.push_tape(TapeEntry {
+                stroke,
+                translation: TapeTranslation::Undo,
+                suggestion: None,
+            });
+            return self.rewrite();
+        }
+
+        // Previous translations which could be part of an outline ending with this stroke
+        let mut combinable = 0;
+        let mut combinable_strokes = 1;
+        let longest_outline = dictionary
+            .longest_outline()
+            .max(self.lesson_dictionary.longest_outline());
+        for translation in self.translations.iter().rev() {
+            combinable_strokes += translation.strokes.len();
+            if combinable_strokes > longest_outline {
+                break;
+            }
+            combinable += 1;
+        }
+
+        let (replaced, strokes, translation) = (0..=combinable)
+            .rev()
+            .find_map(|replaced| {
+                let strokes = self.translations[self.translations.len() - replaced..]
+                    .iter()
+                    .flat_map(|translation| translation.strokes.iter().copied())
+                    .chain([stroke])
+                    .collect::<Vec<_>>();
+                let translation = self
+                    .lesson_dictionary
+                    .lookup(&strokes)
+                    .or_else(|| dictionary.lookup(&strokes))?;
+                Some((replaced, strokes, Some(translation.to_string())))
+            })
+            .unwrap_or((0, vec![stroke], None));
+
+        // Untranslated strokes which are now part of an outline
+        let absorbed = self
+            .translations
+            .drain(self.translations.len() - replaced..)
+            .filter(|translation| !translation.translated)
+            .count();
+        self.stats.untranslated -= absorbed;
+
+        // Untranslated strokes are written as steno
+        let text = match &translation {
+            Some(translation) => translation.clone(),
+            None => {
+                self.stats.untranslated += 1;
+                stroke.to_string()
+            }
+        };
+
+        let suggestion = translation
+            .as_deref()
+            .and_then(|translation| dictionary.outlines(translation).into_iter().next())
+            .filter(|outline| outline.len() < strokes.len())
+            .map(<[Stroke]>::to_vec);
+        self.push_tape(TapeEntry {
+            stroke,
+            translation: match &translation {
+                Some(translation) => TapeTranslation::Translated(translation.clone()),
+                None => TapeTranslation::Untranslated,
+            },
+            suggestion,
+        });
+
+        self.translations.push(Translation {
+            strokes,
+            text,
+            translated: translation.is_some(),
+        });
+        let output = self.rewrite();
+
+        if self.translations.len() > HISTORY_LEN {
+            let removed = self.translations.remove(0);
+            self.written.remove(0);
+            self.formatting = format([removed.text.as_str()], self.formatting).1;
+        }
+
+        output
+    }
+
+    /// Formats the translations again, undoing and rewriting the texts which changed
+    fn rewrite(&mut self) -> TranslatorOutput {
+        let (written, _) = format(
+            self.translations
+                .iter()
+                .map(|translation| translation.text.as_str()),
+            self.formatting,
+        );
+        let unchanged = self
+            .written
+            .iter()
+            .zip(&written)
+            .take_while(|(old, new)| old == new)
+            .count();
+
+        // Nothing is written for empty texts, so there's nothing to undo either
+        let undo = self.written[unchanged..]
+            .iter()
+            .filter(|text| !text.is_empty())
+            .count();
+        let texts = written[unchanged..]
+            .iter()
+            .filter(|text| !text.is_empty())
+            .cloned()
+            .collect();
+        self.written = written;
+
+        TranslatorOutput { undo, texts }
+    }
+
+    fn push_tape(&mut self, entry: TapeEntry) {
+        self.tape.push(entry);
+        if self.tape.len() > HISTORY_LEN {
+            self.tape.remove(0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::steno::format_outline;
+
+    fn translate(
+        translator: &mut Translator,
+        dictionary: &StenoDictionary,
+        steno: &str,
+    ) -> (usize, String) {
+        let output = translator.translate(Stroke::parse(steno).unwrap(), dictionary);
+        (output.undo, output.texts.concat())
+    }
+
+    #[test]
+    fn single_strokes() {
+        let dictionary = StenoDictionary::from_json(r#"{"KAT": "cat", "-T": "the"}"#);
+        let mut translator = Translator::default();
+        assert_eq!(
+            translate(&mut translator, &dictionary, "-T"),
+            (0, "the ".to_string())
+        );
+        assert_eq!(
+            translate(&mut translator, &dictionary, "KAT"),
+            (0, "cat ".to_string())
+        );
+        assert_eq!(
+            translate(&mut translator, &dictionary, "KAOT"),
+            (0, "KAOT ".to_string())
+        );
+    }
+
+    #[test]
+    fn longest_match_replaces_previous_translations() {
+        let dictionary = StenoDictionary::from_json(
+            r#"{"TPHAO": "gnaw", "TPHAO/TKPWUS": "gnus", "A/TPHAO/TKPWUS/-S": "a gnus's"}"#,
+        );
+        let mut translator = Translator::default();
+        assert_eq!(
+            translate(&mut translator, &dictionary, "TPHAO"),
+            (0, "gnaw ".to_string())
+        );
+        assert_eq!(
+            translate(&mut translator, &dictionary, "TKPWUS"),
+            (1, "gnus ".to_string())
+        );
+        assert_eq!(
+            translate(&mut translator, &dictionary, "-S"),
+            (0, "-S ".to_string())
+        );
+
+        let mut translator = Translator::default();
+        for steno in ["A", "TPHAO", "TKPWUS"] {
+            translate(&mut translator, &dictionary, steno);
+        }
+        assert_eq!(
+            translate(&mut translator, &dictionary, "-S"),
+            (2, "a gnus's ".to_string())
+        );
+    }
+
+    #[test]
+    fn tape() {
+        let dictionary = StenoDictionary::from_json(
+            r#"{"TPHAO/TKPWUS": "gnus", "TKPWUS": "gnus", "KAT": "cat"}"#,
+        );
+        let mut translator = Translator::default();
+        for steno in ["KAT", "TPHAO", "TKPWUS"] {
+            translate(&mut translator, &dictionary, steno);
+        }
+
+        let tape = translator
+            .tape()
+            .iter()
+            .map(|entry| {
+                (
+                    entry.stroke.to_string(),
+                    entry.translation.clone(),
+                    entry.suggestion.as_deref().map(format_outline),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tape,
+            [
+                (
+                    "KAT".to_string(),
+                    TapeTranslation::Translated("cat".to_string()),
+                    None
+                ),
+                ("TPHAO".to_string(), TapeTranslation::Untranslated, None),
+                (
+                    "TKPWUS".to_string(),
+                    TapeTranslation::Translated("gnus".to_string()),
+                    Some("TKPWUS".to_string())
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn undo() {
+        let dictionary = StenoDictionary::from_json(r#"{"KAT": "cat", "TPHAO/TKPWUS": "gnus"}"#);
+        let mut translator = Translator::default();
+        assert_eq!(
+            translate(&mut translator, &dictionary, "*"),
+            (0, String::new())
+        );
+        for steno in ["KAT", "TPHAO", "TKPWUS"] {
+            translate(&mut translator, &dictionary, steno);
+        }
+        assert_eq!(
+            translate(&mut translator, &dictionary, "*"),
+            (1, String::new())
+        );
+        assert_eq!(
+            translate(&mut translator, &dictionary, "*"),
+            (1, String::new())
+        );
+        assert_eq!(
+            translate(&mut translator, &dictionary, "*"),
+            (0, String::new())
+        );
+        assert_eq!(
+            translate(&mut translator, &dictionary, "KAT"),
+            (0, "cat ".to_string())
+        );
+    }
+
+    #[test]
+    fn formatting() {
+        let dictionary = StenoDictionary::from_json(
+            r#"{"PHAEUBG": "make", "-G": "{^ing}", "KPA": "{-|}", "TP-PL": "{.}"}"#,
+        );
+        let mut translator = Translator::default();
+        assert_eq!(
+            translate(&mut translator, &dictionary, "KPA"),
+            (0, String::new())
+        );
+        assert_eq!(
+            translate(&mut translator, &dictionary, "PHAEUBG"),
+            (0, "Make ".to_string())
+        );
+        assert_eq!(
+            translate(&mut translator, &dictionary, "-G"),
+            (1, "Making ".to_string())
+        );
+        assert_eq!(
+            translate(&mut translator, &dictionary, "TP-PL"),
+            (1, "ing. ".to_string())
+        );
+        assert_eq!(
+            translate(&mut translator, &dictionary, "*"),
+            (2, "ing ".to_string())
+        );
+        assert_eq!(
+            translate(&mut translator, &dictionary, "*"),
+            (2, "Make ".to_string())
+        );
+    }
+
+    #[test]
+    fn stats() {
+        let dictionary = StenoDictionary::from_json(r#"{"KAT": "cat", "TPHAO/TKPWUS": "gnus"}"#);
+        let mut translator = Translator::default();
+        for steno in ["KAT", "TPHAO", "TKPWUS", "KAOT", "*", "KAT"] {
+            translate(&mut translator, &dictionary, steno);
+        }
+        translator.record_misstroke();
+        let stats = translator.stats();
+        assert_eq!(
+            stats,
+            StenoStats {
+                strokes: 6,
+                undos: 1,
+                untranslated: 0,
+                misstrokes: 1,
+            }
+        );
+        assert_eq!(stats.accuracy(), 4.0 / 6.0 * 100.0);
+        assert_eq!(stats.strokes_per_minute(Duration::from_secs(30)), 12.0);
+        assert_eq!(stats.strokes_per_minute(Duration::ZERO), 0.0);
+    }
+}
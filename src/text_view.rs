@@ -1,192 +1,426 @@
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
+use std::rc::Rc;
+
 use gpui::prelude::*;
+#[cfg(not(target_family = "wasm"))]
+use gpui::Task;
 use gpui::{
-    anchored, div, point, px, AnchoredPositionMode, App, Bounds, ElementId, Entity, FocusHandle,
-    GlobalElementId, InspectorElementId, KeyDownEvent, LayoutId, Pixels, Point, StyledText,
-    TextLayout, TextRun, Window,
+    anchored, div, fill, point, px, relative, size, AnchoredPositionMode, App, Bounds, ElementId,
+    ElementInputHandler, Entity, EntityInputHandler, FocusHandle, Font, FontId, GlobalElementId,
+    GlyphId, Hsla, InspectorElementId, KeyDownEvent, KeyUpEvent, LayoutId, LineLayout, Pixels,
+    Point, Style, TextRun, UTF16Selection, UnderlineStyle, Window,
 };
+use unicode_bidi::Direction;
+use web_time::Instant;
 
 use crate::components::continuous_animation::ContinuousAnimationExt;
 use crate::counter::StartCounterEvent;
 use crate::cursor::Cursor;
 use crate::dictionary::Dictionary;
-use crate::theme::ActiveTheme;
+use crate::keyboard::QWERTY;
+#[cfg(not(target_family = "wasm"))]
+use crate::settings::StenoMachineSettings;
+use crate::settings::{ActiveSettings, StenoHintMode, TestLength, TextSource, TextViewLayout};
+#[cfg(not(target_family = "wasm"))]
+use crate::steno::SerialMachine;
+use crate::steno::{ChordBuilder, StenoLessons, StenoStats, Stroke, TapeEntry, Translator};
+use crate::theme::{ActiveTheme, Theme};
+use crate::typing_session::{Input, RunKind, TypingSession};
 
 pub struct TextView {
-    text: String,
-    char_head: usize,
-    utf8_head: usize,
-    pub typed_chars: usize,
-    over_inserted_stack: Vec<usize>,
-    run_lens: Vec<(bool, usize)>,
+    session: TypingSession,
+    /// Uncommitted text from an input method, shown at the head
+    marked_text: Option<String>,
     focus_handle: FocusHandle,
     cursor: Entity<Cursor>,
-    target_scroll: Pixels,
+    target_scroll: Point<Pixels>,
     animate_scroll: bool,
+    /// Width which lines were last wrapped to
+    wrap_width: Option<Pixels>,
+    /// Text as it was last laid out, until it changes
+    painted_text: Option<PaintedText>,
+    word_widths: WordWidths,
+    chords: ChordBuilder,
+    translator: Translator,
+    last_stroke: Instant,
+    /// Whether the latest stroke typed something wrong
+    misstroked: bool,
+    /// Index of the next drill to add when typing a steno lesson
+    next_drill: usize,
+    /// Task reading from the steno machine, along with the settings it was opened with
+    #[cfg(not(target_family = "wasm"))]
+    machine: Option<(StenoMachineSettings, Task<()>)>,
+    /// Why the steno machine couldn't be read from, until it's opened again
+    #[cfg(not(target_family = "wasm"))]
+    machine_error: Option<String>,
 }
 
 impl TextView {
     pub fn new(focus_handle: FocusHandle, cx: &mut App) -> Entity<Self> {
-        cx.new(|cx| {
+        let text_view = cx.new(|cx| {
+            Self::refresh_steno_hint(cx);
+            let mut next_drill = 0;
+
             Self {
-                text: Dictionary::random_text(50, cx),
-                char_head: 0,
-                utf8_head: 0,
-                typed_chars: 0,
-                over_inserted_stack: vec![0],
-                run_lens: Vec::new(),
+                session: TypingSession::new(Self::initial_text(&mut next_drill, cx)),
+                marked_text: None,
                 focus_handle,
                 cursor: Cursor::new(cx),
-                target_scroll: px(0.0),
+                target_scroll: Point::default(),
                 animate_scroll: true,
+                wrap_width: None,
+                painted_text: None,
+                word_widths: WordWidths::default(),
+                chords: ChordBuilder::default(),
+                translator: Self::translator(cx),
+                last_stroke: Instant::now(),
+                misstroked: false,
+                next_drill,
+                #[cfg(not(target_family = "wasm"))]
+                machine: None,
+                #[cfg(not(target_family = "wasm"))]
+                machine_error: None,
             }
-        })
+        });
+        #[cfg(not(target_family = "wasm"))]
+        text_view.update(cx, |text_view, cx| text_view.connect_machine(cx));
+
+        text_view
     }
 
-    fn add_run(&mut self, correct: bool, utf8_len: usize, char_len: usize) {
-        if let Some((last_run_correct, last_run)) = self.run_lens.last_mut() {
-            if *last_run_correct == correct {
-                *last_run += utf8_len;
-            } else {
-                self.run_lens.push((correct, utf8_len));
-            }
-        } else {
-            self.run_lens.push((correct, utf8_len));
+    pub fn session(&self) -> &TypingSession {
+        &self.session
+    }
+
+    pub fn steno_tape(&self) -> &[TapeEntry] {
+        self.translator.tape()
+    }
+
+    pub fn steno_stats(&self) -> StenoStats {
+        self.translator.stats()
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    pub fn machine_error(&self) -> Option<&str> {
+        self.machine_error.as_deref()
+    }
+
+    /// Target word which the next stroke should write
+    pub fn upcoming_word(&self) -> &str {
+        &self.session.text()[self.session.active_word()]
+    }
+
+    /// Whether to show outlines for the upcoming word
+    pub fn show_steno_hint(&self, mode: StenoHintMode) -> bool {
+        match mode {
+            StenoHintMode::Always => true,
+            StenoHintMode::AfterDelay(delay) => self.last_stroke.elapsed() >= delay,
+            StenoHintMode::AfterMisstroke => self.misstroked,
         }
+    }
 
-        self.utf8_head += utf8_len;
-        self.char_head += char_len;
+    /// Starts over with new text, such as after changing the test length, and follows any changes
+    /// to the steno settings
+    pub fn restart(&mut self, cx: &mut Context<Self>) {
+        self.next_drill = 0;
+        self.session = TypingSession::new(Self::initial_text(&mut self.next_drill, cx));
+        self.marked_text = None;
+        self.target_scroll = Point::default();
+        self.painted_text = None;
+        self.chords = ChordBuilder::default();
+        self.translator = Self::translator(cx);
+        self.misstroked = false;
+        self.last_stroke = Instant::now();
+        #[cfg(not(target_family = "wasm"))]
+        self.connect_machine(cx);
+        Self::refresh_steno_hint(cx);
+        cx.notify();
     }
 
-    fn add_whitespace(&mut self, whitespace: &str) {
-        let end_of_word: usize = self
-            .text
-            .char_indices()
-            .take_while(|(idx, char)| *idx < self.char_head || !char.is_whitespace())
-            .map(|(_, char)| char.len_utf8())
-            .sum();
+    /// Text to start a test with. Word count tests get all of their words at the start, with a
+    /// space after the last word so that it's finished like the others.
+    fn initial_text(next_drill: &mut usize, cx: &mut App) -> String {
+        match cx.settings().test_length {
+            TestLength::Time(_) => Self::source_text(next_drill, 50, cx),
+            TestLength::Words(words) => format!("{} ", Self::source_text(next_drill, words, cx)),
+        }
+    }
 
-        // Add run for invalid chars
-        if end_of_word > self.utf8_head {
-            self.add_run(
-                false,
-                end_of_word - self.utf8_head,
-                self.text[self.utf8_head..end_of_word].chars().count(),
-            );
+    /// Words to type from the configured text source, continuing from `next_drill` for lessons
+    fn source_text(next_drill: &mut usize, word_count: usize, cx: &mut App) -> String {
+        if let TextSource::StenoLesson(id) = &cx.settings().text_source {
+            if let Some(lesson) = cx.global::<StenoLessons>().lesson(id) {
+                let (text, drills) = lesson.text(*next_drill, word_count);
+                *next_drill += drills;
+                return text;
+            }
         }
 
-        // Replace whitespace with written whitespace
-        let replace_len: usize = self
-            .text
-            .chars()
-            .skip(self.char_head)
-            .take_while(|char| char.is_whitespace())
-            .map(char::len_utf8)
-            .sum();
-        self.text
-            .replace_range(end_of_word..end_of_word + replace_len, whitespace);
-
-        // Add run for whitespace
-        self.add_run(
-            replace_len != 0,
-            whitespace.len(),
-            whitespace.chars().count(),
-        );
+        Dictionary::random_text(word_count, cx)
+    }
 
-        // Advance over inserted stack
-        self.over_inserted_stack.push(0);
+    /// Translator for the configured text source, which knows a lesson's drills that are written
+    /// as steno so that they aren't untranslated
+    fn translator(cx: &App) -> Translator {
+        if let TextSource::StenoLesson(id) = &cx.settings().text_source {
+            if let Some(lesson) = cx.global::<StenoLessons>().lesson(id) {
+                return Translator::new(lesson.steno_drills());
+            }
+        }
+
+        Translator::default()
     }
 
-    fn backspace(&mut self) {
-        if self.char_head == 0 {
+    /// Notifies once a delayed hint should be shown
+    fn refresh_steno_hint(cx: &mut Context<Self>) {
+        let steno = &cx.settings().steno;
+        let Some(StenoHintMode::AfterDelay(delay)) = steno.hints.filter(|_| steno.enabled) else {
             return;
-        }
+        };
+
+        cx.spawn(async move |text_view, cx| {
+            cx.background_executor().timer(delay).await;
+            text_view.update(cx, |_text_view, cx| cx.notify()).ok();
+        })
+        .detach();
+    }
 
-        let unwind_len = self
-            .text
-            .chars()
-            .nth(self.char_head - 1)
-            .unwrap()
-            .len_utf8();
-
-        // Remove text
-        let over_inserted = self.over_inserted_stack.last_mut().unwrap();
-        if *over_inserted != 0 {
-            self.text
-                .replace_range(self.utf8_head - unwind_len..self.utf8_head, "");
-            *over_inserted -= unwind_len;
+    /// Text exposed to input methods: the typed text followed by any marked text
+    fn input_document(&self) -> String {
+        let mut document = self.session.text()[..self.session.head()].to_string();
+        document.push_str(self.marked_text.as_deref().unwrap_or_default());
+        document
+    }
+
+    /// Reads strokes from the configured steno machine in the background, handling them like
+    /// chords from the keyboard. The machine is only reopened if its settings have changed or it
+    /// couldn't be read from.
+    #[cfg(not(target_family = "wasm"))]
+    fn connect_machine(&mut self, cx: &mut Context<Self>) {
+        let steno = &cx.settings().steno;
+        let settings = steno.machine.clone().filter(|_| steno.enabled);
+        let connected = self.machine.as_ref().map(|(connected, _)| connected);
+        if connected == settings.as_ref() && self.machine_error.is_none() {
+            return;
         }
+        self.machine = None;
+        self.machine_error = None;
+        let Some(settings) = settings else {
+            return;
+        };
 
-        // Remove runs
-        let delete = if let Some((_, last_run_len)) = self.run_lens.last_mut() {
-            *last_run_len -= unwind_len;
-            *last_run_len == 0
-        } else {
-            false
+        let mut machine = match SerialMachine::open(&settings.port, settings.protocol) {
+            Ok(machine) => machine,
+            Err(err) => {
+                println!("Failed to open steno machine `{}`: {err}", settings.port);
+                self.machine_error = Some(format!("failed to open {}: {err}", settings.port));
+                return;
+            }
         };
-        if delete {
-            self.run_lens.pop();
+
+        let port = settings.port.clone();
+        let task = cx.spawn(async move |text_view, cx| {
+            loop {
+                let (returned_machine, strokes) = cx
+                    .background_executor()
+                    .spawn(async move {
+                        let strokes = machine.read_strokes();
+                        (machine, strokes)
+                    })
+                    .await;
+                machine = returned_machine;
+
+                let strokes = match strokes {
+                    Ok(strokes) => strokes,
+                    Err(err) => {
+                        println!("Lost connection to steno machine `{port}`: {err}");
+                        text_view
+                            .update(cx, |text_view, cx| {
+                                text_view.machine_error = Some(format!("lost {port}: {err}"));
+                                cx.notify();
+                            })
+                            .ok();
+                        break;
+                    }
+                };
+                let updated = text_view.update(cx, |text_view, cx| {
+                    for stroke in strokes {
+                        text_view.handle_stroke(stroke, cx);
+                    }
+                });
+                if updated.is_err() {
+                    break;
+                }
+            }
+        });
+        self.machine = Some((settings, task));
+    }
+
+    /// Types the translation of a stroke, undoing the texts which it changes
+    fn handle_stroke(&mut self, stroke: Stroke, cx: &mut Context<Self>) {
+        cx.emit(StartCounterEvent);
+        let output = self.translator.translate(stroke, cx.global());
+        let incorrect_keystrokes = self.session.stats().incorrect_keystrokes;
+        let typed = !output.texts.is_empty();
+        self.session.handle_input(Input::Stroke {
+            undo: output.undo,
+            texts: output.texts,
+        });
+        if typed {
+            self.misstroked = self.session.stats().incorrect_keystrokes > incorrect_keystrokes;
+            if self.misstroked {
+                self.translator.record_misstroke();
+            }
         }
 
-        self.utf8_head -= unwind_len;
-        self.char_head -= 1;
+        self.last_stroke = Instant::now();
+        Self::refresh_steno_hint(cx);
+        cx.notify();
     }
 
-    fn fruncate_text(&mut self, utf8_len: usize) {
-        let char_len = self.text[..utf8_len].chars().count();
+    /// Text typed by a key press when emulating a layout, or `None` to leave the system's layout
+    /// to type it through the input handler
+    fn emulate_key(&self, event: &KeyDownEvent, cx: &App) -> Option<String> {
+        let settings = cx.settings();
+        let emulated_layout = settings.emulated_layout.as_ref()?;
+        let keystroke = &event.keystroke;
+        let modifiers = keystroke.modifiers;
+        if self.marked_text.is_some()
+            || modifiers.control
+            || modifiers.alt
+            || modifiers.platform
+            || modifiers.function
+        {
+            return None;
+        }
 
-        // Fruncate runs
-        let (run_offset, run_len, run_idx) = self
-            .run_lens
-            .iter()
-            .enumerate()
-            .scan(0, |offset_acc, (run_idx, (_correct, run_len))| {
-                let offset = *offset_acc;
-                *offset_acc += run_len;
-                Some((offset, *run_len, run_idx))
-            })
-            .find(|(offset, run_len, _)| (*offset..offset + run_len).contains(&utf8_len))
-            .unwrap();
-        self.run_lens.drain(0..run_idx);
-        self.run_lens[0].1 = run_offset + run_len - utf8_len;
-
-        // Remove stack entries
-        let words_completed = self
-            .text
-            .chars()
-            .take(char_len)
-            .filter(|char| char.is_whitespace())
-            .count();
-        self.over_inserted_stack.drain(0..words_completed);
-
-        // Remove text
-        self.text.drain(0..utf8_len);
-
-        // Move head
-        self.char_head -= char_len;
-        self.utf8_head -= utf8_len;
-        self.target_scroll = px(0.0);
+        let mut chars = keystroke.key.chars();
+        let (Some(key), None) = (chars.next(), chars.next()) else {
+            return None;
+        };
+        settings
+            .keyboard_layout
+            .emulate(emulated_layout, key, modifiers.shift)
+            .map(String::from)
+    }
+
+    /// Key at the same position on QWERTY as `key` on the system's layout, since steno keymaps
+    /// are written for key positions rather than the characters they type
+    fn steno_key(key: &str, cx: &App) -> String {
+        let mut chars = key.chars();
+        let (Some(char), None) = (chars.next(), chars.next()) else {
+            return key.to_string();
+        };
+        cx.settings()
+            .keyboard_layout
+            .emulate(&QWERTY, char, false)
+            .map_or_else(|| key.to_string(), String::from)
+    }
+
+    /// Removes the first `utf8_len` bytes of text, which were scrolled out of view by
+    /// `scroll_offset`
+    fn fruncate_text(&mut self, utf8_len: usize, scroll_offset: Point<Pixels>) {
+        self.session.fruncate(utf8_len);
+        self.painted_text = None;
+        self.target_scroll = self.target_scroll - scroll_offset;
         self.animate_scroll = false;
     }
+
+    fn text_runs(
+        &self,
+        font: Font,
+        placeholder_color: Hsla,
+        blind: bool,
+        theme: &Theme,
+    ) -> Vec<TextRun> {
+        let session = &self.session;
+        let active_word = session.active_word();
+        let error_words = session
+            .word_ranges(0..active_word.start)
+            .filter(|word| {
+                !blind
+                    && session.runs().any(|(kind, run)| {
+                        kind != RunKind::Correct && run.start < word.end && word.start < run.end
+                    })
+            })
+            .collect::<Vec<_>>();
+
+        // Split the text wherever the style can change
+        let mut boundaries = session
+            .runs()
+            .map(|(_, run)| run.end)
+            .chain(
+                error_words
+                    .iter()
+                    .chain([&active_word])
+                    .flat_map(|range| [range.start, range.end]),
+            )
+            .chain([0, session.head(), session.text().len()])
+            .collect::<Vec<_>>();
+        boundaries.sort_unstable();
+        boundaries.dedup();
+
+        boundaries
+            .windows(2)
+            .map(|window| {
+                let start = window[0];
+                let kind = session
+                    .runs()
+                    .find(|(_, run)| run.contains(&start))
+                    .map(|(kind, _)| kind);
+
+                TextRun {
+                    len: window[1] - start,
+                    font: font.clone(),
+                    color: match kind {
+                        Some(_) if blind => theme.text_view_blind_text.into(),
+                        Some(RunKind::Correct) => theme.text_view_correct_text.into(),
+                        Some(RunKind::Incorrect) => theme.text_view_incorrect_text.into(),
+                        Some(RunKind::Extra) => theme.text_view_extra_text.into(),
+                        Some(RunKind::Missed) => theme.text_view_missed_text.into(),
+                        None => placeholder_color,
+                    },
+                    background_color: active_word
+                        .contains(&start)
+                        .then(|| theme.text_view_active_word_background.into()),
+                    underline: error_words
+                        .iter()
+                        .any(|word| word.contains(&start))
+                        .then(|| {
+                            UnderlineStyle {
+                                thickness: px(2.0),
+                                color: Some(theme.text_view_error_underline.into()),
+                                wavy: theme.text_view_error_underline_wavy,
+                            }
+                        }),
+                    strikethrough: None,
+                }
+            })
+            .collect()
+    }
 }
 
 impl Render for TextView {
     fn render(&mut self, window: &mut Window, cx: &mut Context<'_, Self>) -> impl IntoElement {
+        let settings = cx.settings().text_view;
         let target_scroll = self.target_scroll;
         let animate_scroll = self.animate_scroll;
         let window_active = window.is_window_active();
         let entity = cx.entity().downgrade();
+        let rtl = unicode_bidi::get_base_direction(self.session.text()) == Direction::Rtl;
 
         div()
             .track_focus(&self.focus_handle)
-            .text_3xl()
-            .font_family("Sans")
+            .text_size(settings.text_size)
+            .font_family(settings.font.family())
             .text_color(cx.theme().text_view_placeholder_text)
             .child(div().with_continuous_animation(
                 "text-entry-animation",
-                px(0.0),
+                Point::default(),
                 move |element, current_scroll, delta, window, _cx| {
-                    let magnitude = (*current_scroll - target_scroll).abs();
-                    let animating = if magnitude > px(0.5) && window_active && animate_scroll {
+                    let magnitude = (*current_scroll - target_scroll).magnitude();
+                    let animating = if magnitude > 0.5 && window_active && animate_scroll {
                         let mix = (delta * 15.0).clamp(0.0, 1.0);
                         *current_scroll = *current_scroll * (1.0 - mix) + target_scroll * mix;
                         true
@@ -198,12 +432,21 @@ impl Render for TextView {
                     (
                         element
                             .w_full()
-                            .h(window.line_height() * 3)
+                            .map(|element| {
+                                match settings.layout {
+                                    TextViewLayout::Wrapped { visible_lines } => {
+                                        element.h(window.line_height() * visible_lines as f32)
+                                    }
+                                    TextViewLayout::Tape { .. } => element.h(window.line_height()),
+                                }
+                            })
+                            .whitespace_nowrap()
                             .overflow_hidden()
                             .child(TextViewElement {
                                 entity: entity.upgrade().unwrap(),
                                 scroll: *current_scroll,
                                 scrolling: animating,
+                                rtl,
                             }),
                         animating,
                     )
@@ -221,64 +464,374 @@ impl Render for TextView {
                 },
             )
             .on_key_down(cx.listener(|this, event: &KeyDownEvent, _window, cx| {
-                cx.emit(StartCounterEvent);
-
-                match (
-                    this.text.chars().nth(this.char_head),
-                    event.keystroke.key.as_str(),
-                    event.keystroke.key_char.as_deref(),
-                ) {
-                    (_, "backspace", _) => {
-                        let is_whitespace = this
-                            .text
-                            .chars()
-                            .nth(this.char_head.saturating_sub(1))
-                            .is_some_and(|char| char.is_whitespace());
-
-                        this.backspace();
-
-                        if is_whitespace && this.char_head != 0 {
-                            this.over_inserted_stack.pop();
-                        }
-                    }
-                    (_, _, Some(whitespace))
-                        if whitespace.chars().all(|char| char.is_whitespace()) =>
-                    {
-                        if this.char_head == 0
-                            || this
-                                .text
-                                .chars()
-                                .nth(this.char_head - 1)
-                                .is_some_and(|char| !char.is_whitespace())
-                        {
-                            this.add_whitespace(whitespace);
-                        }
-                    }
-                    (Some(replaced), _, Some(replace_with)) if !replaced.is_whitespace() => {
-                        let correct = replaced.to_string() == *replace_with;
-                        this.add_run(correct, replaced.len_utf8(), replace_with.chars().count());
+                let key = Self::steno_key(&event.keystroke.key, cx);
+                let steno = &cx.settings().steno;
+                if steno.enabled
+                    && !event.keystroke.modifiers.modified()
+                    && this.chords.key_down(&steno.keymap, &key)
+                {
+                    cx.stop_propagation();
+                    return;
+                }
 
-                        if correct {
-                            this.typed_chars += replace_with.chars().count();
-                        }
-                    }
-                    (_, _, Some(to_insert)) => {
-                        this.text.insert_str(this.utf8_head, to_insert);
-                        this.add_run(false, to_insert.len(), to_insert.chars().count());
-                        *this.over_inserted_stack.last_mut().unwrap() += to_insert.len();
-                    }
-                    _ => {}
+                if let Some(text) = this.emulate_key(event, cx) {
+                    cx.emit(StartCounterEvent);
+                    this.session.handle_input(Input::Text(text));
+                    cx.stop_propagation();
+                    cx.notify();
+                    return;
                 }
 
-                cx.notify();
+                // Text is otherwise received through the input handler
+                if event.keystroke.key == "backspace" && this.marked_text.is_none() {
+                    cx.emit(StartCounterEvent);
+                    this.session.handle_input(Input::Backspace);
+                    cx.stop_propagation();
+                    cx.notify();
+                }
+            }))
+            .on_key_up(cx.listener(|this, event: &KeyUpEvent, _window, cx| {
+                let key = Self::steno_key(&event.keystroke.key, cx);
+                if let Some(stroke) = this.chords.key_up(&key) {
+                    this.handle_stroke(stroke, cx);
+                }
             }))
     }
 }
 
+impl EntityInputHandler for TextView {
+    fn text_for_range(
+        &mut self,
+        range_utf16: Range<usize>,
+        adjusted_range: &mut Option<Range<usize>>,
+        _window: &mut Window,
+        _cx: &mut Context<Self>,
+    ) -> Option<String> {
+        let document = self.input_document();
+        let range = range_from_utf16(&document, &range_utf16);
+        adjusted_range.replace(range_to_utf16(&document, &range));
+        Some(document[range].to_string())
+    }
+
+    fn selected_text_range(
+        &mut self,
+        _ignore_disabled_input: bool,
+        _window: &mut Window,
+        _cx: &mut Context<Self>,
+    ) -> Option<UTF16Selection> {
+        let document_len = self.input_document().encode_utf16().count();
+        Some(UTF16Selection {
+            range: document_len..document_len,
+            reversed: false,
+        })
+    }
+
+    fn marked_text_range(
+        &self,
+        _window: &mut Window,
+        _cx: &mut Context<Self>,
+    ) -> Option<Range<usize>> {
+        self.marked_text.as_ref().map(|marked_text| {
+            let start = self.session.text()[..self.session.head()]
+                .encode_utf16()
+                .count();
+            start..start + marked_text.encode_utf16().count()
+        })
+    }
+
+    fn unmark_text(&mut self, _window: &mut Window, cx: &mut Context<Self>) {
+        self.marked_text = None;
+        cx.notify();
+    }
+
+    fn replace_text_in_range(
+        &mut self,
+        range_utf16: Option<Range<usize>>,
+        text: &str,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        cx.emit(StartCounterEvent);
+
+        // Replacing already committed text, such as when picking an accent for the last character.
+        // Text can only be typed at the head, so anything after the range is typed again.
+        let mut text = text.to_string();
+        if let Some(range_utf16) = range_utf16.filter(|_| self.marked_text.is_none()) {
+            let document = self.input_document();
+            let range = range_from_utf16(&document, &range_utf16);
+            while self.session.head() > range.start {
+                let head = self.session.head();
+                self.session.handle_input(Input::Backspace);
+                if self.session.head() == head {
+                    break;
+                }
+            }
+
+            // Backspaces remove whole keystrokes, which can start before the range
+            let head = self.session.head().min(range.start);
+            text = format!(
+                "{}{text}{}",
+                &document[head..range.start],
+                &document[range.end.max(range.start)..]
+            );
+        }
+
+        self.marked_text = None;
+        self.session.handle_input(Input::Text(text));
+        cx.notify();
+    }
+
+    fn replace_and_mark_text_in_range(
+        &mut self,
+        _range_utf16: Option<Range<usize>>,
+        new_text: &str,
+        _new_selected_range: Option<Range<usize>>,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        cx.emit(StartCounterEvent);
+        self.marked_text = (!new_text.is_empty()).then(|| new_text.to_string());
+        cx.notify();
+    }
+
+    fn bounds_for_range(
+        &mut self,
+        _range_utf16: Range<usize>,
+        _element_bounds: Bounds<Pixels>,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Option<Bounds<Pixels>> {
+        // Place candidate windows at the cursor
+        let cursor = self.cursor.read(cx);
+        Some(Bounds::new(
+            cursor.text_origin + cursor.target_position - point(px(0.0), cursor.line_height),
+            size(cursor.line_height / 3.0, cursor.line_height),
+        ))
+    }
+
+    fn character_index_for_point(
+        &mut self,
+        point: Point<Pixels>,
+        _window: &mut Window,
+        _cx: &mut Context<Self>,
+    ) -> Option<usize> {
+        let index = self.painted_text.as_ref()?.index_for_point(point)?;
+        // Text past the head isn't part of the document
+        let document = self.input_document();
+        Some(document[..index.min(document.len())].encode_utf16().count())
+    }
+}
+
 struct TextViewElement {
     entity: Entity<TextView>,
-    scroll: Pixels,
+    scroll: Point<Pixels>,
     scrolling: bool,
+    rtl: bool,
+}
+
+struct TextViewLayoutState {
+    lines: Rc<[TextLine]>,
+    /// Byte offsets at which each line starts
+    line_starts: Vec<usize>,
+}
+
+/// Widths of words as shaped in a style, so that wrapping doesn't shape every word again on each
+/// layout
+#[derive(Default)]
+struct WordWidths {
+    /// Font, font size and letter spacing which the words were shaped with
+    style: Option<(Font, Pixels, Pixels)>,
+    widths: HashMap<String, Pixels>,
+}
+
+impl WordWidths {
+    /// Widths of words shaped in a style, forgetting the widths from any other style
+    fn for_style(
+        &mut self,
+        font: &Font,
+        font_size: Pixels,
+        letter_spacing: Pixels,
+    ) -> &mut HashMap<String, Pixels> {
+        let style = (font.clone(), font_size, letter_spacing);
+        if self.style.as_ref() != Some(&style) {
+            self.style = Some(style);
+            self.widths.clear();
+        }
+        &mut self.widths
+    }
+}
+
+/// Lines of text along with where they were painted, for finding the text under a point
+struct PaintedText {
+    lines: Rc<[TextLine]>,
+    line_starts: Vec<usize>,
+    /// Bounds of the text after scrolling
+    bounds: Bounds<Pixels>,
+    line_height: Pixels,
+    rtl: bool,
+}
+
+impl PaintedText {
+    /// Byte offset of the text under `point`
+    fn index_for_point(&self, point: Point<Pixels>) -> Option<usize> {
+        let line_y = (point.y - self.bounds.origin.y) / self.line_height;
+        if line_y < 0.0 {
+            return None;
+        }
+        let line_idx = line_y as usize;
+        let line = self.lines.get(line_idx)?;
+
+        let align_offset = if self.rtl {
+            self.bounds.size.width - line.width
+        } else {
+            px(0.0)
+        };
+        let x = point.x - self.bounds.origin.x - align_offset;
+        line.glyphs
+            .iter()
+            .find(|glyph| glyph.extent.contains(&x))
+            .map(|glyph| self.line_starts[line_idx] + glyph.index)
+    }
+}
+
+/// A glyph of a [`TextLine`], positioned with letter spacing
+struct LineGlyph {
+    font_id: FontId,
+    id: GlyphId,
+    is_emoji: bool,
+    /// Byte offset of the glyph's text within the line
+    index: usize,
+    /// Horizontal extent, up to the start of the next glyph
+    extent: Range<Pixels>,
+}
+
+/// A line of text which is shaped and painted separately from the others. Glyphs are painted
+/// individually, as gpui has no letter spacing of its own.
+struct TextLine {
+    /// Glyphs in visual order
+    glyphs: Vec<LineGlyph>,
+    width: Pixels,
+    ascent: Pixels,
+    descent: Pixels,
+    font_size: Pixels,
+    /// Styles of the line's text
+    runs: Vec<TextRun>,
+}
+
+impl TextLine {
+    fn new(layout: &LineLayout, runs: Vec<TextRun>, letter_spacing: Pixels) -> Self {
+        let positions = layout
+            .runs
+            .iter()
+            .flat_map(|run| run.glyphs.iter().map(move |glyph| (run.font_id, glyph)))
+            .enumerate()
+            .map(|(glyph_idx, (font_id, glyph))| {
+                (
+                    font_id,
+                    glyph,
+                    glyph.position.x + letter_spacing * glyph_idx as f32,
+                )
+            })
+            .collect::<Vec<_>>();
+        let width = layout.width + letter_spacing * positions.len() as f32;
+        let glyphs = positions
+            .iter()
+            .enumerate()
+            .map(|(glyph_idx, &(font_id, glyph, x))| {
+                LineGlyph {
+                    font_id,
+                    id: glyph.id,
+                    is_emoji: glyph.is_emoji,
+                    index: glyph.index,
+                    extent: x..positions.get(glyph_idx + 1).map_or(width, |next| next.2),
+                }
+            })
+            .collect();
+
+        Self {
+            glyphs,
+            width,
+            ascent: layout.ascent,
+            descent: layout.descent,
+            font_size: layout.font_size,
+            runs,
+        }
+    }
+
+    /// Horizontal extent of the glyphs for `range`. Glyphs are in visual order, so this works for
+    /// both left-to-right and right-to-left runs.
+    fn glyph_extent(&self, range: Range<usize>) -> Option<Range<Pixels>> {
+        self.glyphs
+            .iter()
+            .filter(|glyph| range.contains(&glyph.index))
+            .map(|glyph| glyph.extent.clone())
+            .reduce(|extent, glyph| extent.start.min(glyph.start)..extent.end.max(glyph.end))
+    }
+
+    /// Index of the run styling the text at `index`
+    fn run_idx(&self, index: usize) -> usize {
+        self.runs
+            .iter()
+            .scan(0, |run_end, run| {
+                *run_end += run.len;
+                Some(*run_end)
+            })
+            .take_while(|&run_end| run_end <= index)
+            .count()
+            .min(self.runs.len().saturating_sub(1))
+    }
+
+    fn paint(&self, origin: Point<Pixels>, line_height: Pixels, window: &mut Window) {
+        let baseline = origin.y + (line_height - self.ascent - self.descent) / 2.0 + self.ascent;
+
+        // Backgrounds and underlines span neighbouring glyphs with the same run
+        let mut spans: Vec<(usize, Range<Pixels>)> = Vec::new();
+        for glyph in &self.glyphs {
+            let run_idx = self.run_idx(glyph.index);
+            match spans.last_mut() {
+                Some((span_run_idx, span))
+                    if *span_run_idx == run_idx && span.end == glyph.extent.start =>
+                {
+                    span.end = glyph.extent.end;
+                }
+                _ => spans.push((run_idx, glyph.extent.clone())),
+            }
+        }
+        for (run_idx, span) in spans {
+            let run = &self.runs[run_idx];
+            if let Some(background) = run.background_color {
+                window.paint_quad(fill(
+                    Bounds::from_corners(
+                        point(origin.x + span.start, origin.y),
+                        point(origin.x + span.end, origin.y + line_height),
+                    ),
+                    background,
+                ));
+            }
+            if let Some(underline) = &run.underline {
+                window.paint_underline(
+                    point(origin.x + span.start, baseline + self.descent * 0.618),
+                    span.end - span.start,
+                    &UnderlineStyle {
+                        thickness: underline.thickness,
+                        color: Some(underline.color.unwrap_or(run.color)),
+                        wavy: underline.wavy,
+                    },
+                );
+            }
+        }
+
+        for glyph in &self.glyphs {
+            let glyph_origin = point(origin.x + glyph.extent.start, baseline);
+            let painted = if glyph.is_emoji {
+                window.paint_emoji(glyph_origin, glyph.font_id, glyph.id, self.font_size)
+            } else {
+                let color = self.runs[self.run_idx(glyph.index)].color;
+                window.paint_glyph(glyph_origin, glyph.font_id, glyph.id, self.font_size, color)
+            };
+            painted.ok();
+        }
+    }
 }
 
 impl IntoElement for TextViewElement {
@@ -290,8 +843,8 @@ impl IntoElement for TextViewElement {
 }
 
 impl Element for TextViewElement {
-    type PrepaintState = ();
-    type RequestLayoutState = StyledText;
+    type PrepaintState = Bounds<Pixels>;
+    type RequestLayoutState = TextViewLayoutState;
 
     fn id(&self) -> Option<ElementId> {
         None
@@ -311,38 +864,106 @@ impl Element for TextViewElement {
         let text_style = window.text_style();
         let text_view = self.entity.read(cx);
 
-        // Create styled text
-        let runs = text_view
-            .run_lens
-            .iter()
-            .map(|(correct, run_len)| {
+        let mut text = text_view.session.text().to_string();
+        let mut runs = text_view.text_runs(
+            text_style.font(),
+            text_style.color,
+            cx.settings().text_view.blind,
+            cx.theme(),
+        );
+
+        // Show marked text inline at the head
+        if let Some(marked_text) = &text_view.marked_text {
+            let head = text_view.session.head();
+            let run_idx = runs
+                .iter()
+                .scan(0, |offset, run| {
+                    *offset += run.len;
+                    Some(*offset)
+                })
+                .take_while(|&run_end| run_end <= head)
+                .count();
+
+            text.insert_str(head, marked_text);
+            runs.insert(
+                run_idx,
                 TextRun {
-                    len: *run_len,
+                    len: marked_text.len(),
                     font: text_style.font(),
-                    color: if *correct {
-                        cx.theme().text_view_correct_text.into()
-                    } else {
-                        cx.theme().text_view_incorrect_text.into()
-                    },
+                    color: cx.theme().text_view_marked_text.into(),
                     background_color: None,
-                    underline: None,
+                    underline: Some(UnderlineStyle {
+                        thickness: px(1.0),
+                        color: Some(cx.theme().text_view_marked_text.into()),
+                        wavy: false,
+                    }),
                     strikethrough: None,
+                },
+            );
+        }
+
+        // Wrap lines ourselves, as gpui wraps glyphs in visual order which breaks right-to-left
+        // text. Each line is then shaped separately, which also reorders bidi text per line.
+        let settings = cx.settings().text_view;
+        let wrap_width = match settings.layout {
+            TextViewLayout::Wrapped { .. } => text_view.wrap_width,
+            TextViewLayout::Tape { .. } => None,
+        };
+        let font_size = text_style.font_size.to_pixels(window.rem_size());
+        let line_starts = self.entity.update(cx, |text_view, _cx| {
+            let word_widths = text_view.word_widths.for_style(
+                &text_style.font(),
+                font_size,
+                settings.letter_spacing,
+            );
+            line_starts(&text, wrap_width, |word| {
+                if let Some(&width) = word_widths.get(word) {
+                    return width;
                 }
+
+                let run = TextRun {
+                    len: word.len(),
+                    font: text_style.font(),
+                    color: text_style.color,
+                    background_color: None,
+                    underline: None,
+                    strikethrough: None,
+                };
+                let shaped = window.text_system().shape_line(
+                    word.to_string().into(),
+                    font_size,
+                    &[run],
+                    None,
+                );
+                let width = TextLine::new(&shaped, Vec::new(), settings.letter_spacing).width;
+                word_widths.insert(word.to_string(), width);
+                width
             })
-            .chain([TextRun {
-                len: text_view.text.len() - text_view.utf8_head,
-                font: text_style.font(),
-                color: text_style.color,
-                background_color: None,
-                underline: None,
-                strikethrough: None,
-            }])
-            .collect();
+        });
 
-        let mut styled_text = StyledText::new(&text_view.text).with_runs(runs);
+        let lines = line_starts
+            .iter()
+            .enumerate()
+            .map(|(line_idx, &line_start)| {
+                let line_end = line_starts.get(line_idx + 1).copied().unwrap_or(text.len());
+                let line_text = text[line_start..line_end].trim_end_matches('\n');
+                let line_runs = line_runs(&runs, line_start..line_start + line_text.len());
+                let shaped = window.text_system().shape_line(
+                    line_text.to_string().into(),
+                    font_size,
+                    &line_runs,
+                    None,
+                );
+                TextLine::new(&shaped, line_runs, settings.letter_spacing)
+            })
+            .collect::<Rc<[_]>>();
+
+        let mut style = Style::default();
+        style.size.width = relative(1.0).into();
+        style.size.height = (window.line_height() * lines.len() as f32).into();
         (
-            styled_text.request_layout(None, None, window, cx).0,
-            styled_text,
+            window.request_layout(style, [], cx),
+            TextViewLayoutState { lines, line_starts },
         )
     }
 
@@ -351,35 +972,81 @@ impl Element for TextViewElement {
         _id: Option<&GlobalElementId>,
         _inspector_id: Option<&InspectorElementId>,
         bounds: Bounds<Pixels>,
-        styled_text: &mut Self::RequestLayoutState,
+        layout_state: &mut Self::RequestLayoutState,
         window: &mut Window,
         cx: &mut App,
     ) -> Self::PrepaintState {
-        let scrolled_bounds = bounds - point(px(0.0), self.scroll);
-        styled_text.prepaint(None, None, scrolled_bounds, &mut (), window, cx);
+        let TextViewLayoutState { lines, line_starts } = layout_state;
+        let settings = cx.settings().text_view;
+        // Word count tests have all of their text from the start
+        let endless = matches!(cx.settings().test_length, TestLength::Time(_));
+        let line_height = window.line_height();
+
+        // Right-to-left text is anchored to the right edge of the view and scrolls rightwards
+        let text_width = match settings.layout {
+            TextViewLayout::Wrapped { .. } => bounds.size.width,
+            TextViewLayout::Tape { .. } => lines[0].width,
+        };
+        let text_size = size(text_width, bounds.size.height);
+        let scrolled_bounds = if self.rtl {
+            Bounds::new(
+                point(
+                    bounds.right() + self.scroll.x - text_width,
+                    bounds.origin.y - self.scroll.y,
+                ),
+                text_size,
+            )
+        } else {
+            Bounds::new(bounds.origin - self.scroll, text_size)
+        };
 
         self.entity.update(cx, |text_view, cx| {
-            let utf8_len = text_view
-                .text
-                .chars()
-                .nth(text_view.char_head)
-                .map_or(1, char::len_utf8);
-            let (glyph_position, cursor_position) = cursor_pos(
-                text_view.utf8_head,
-                utf8_len,
-                styled_text.layout(),
-                window.line_height() / 3.0,
-            );
+            text_view.painted_text = Some(PaintedText {
+                lines: lines.clone(),
+                line_starts: line_starts.clone(),
+                bounds: scrolled_bounds,
+                line_height,
+                rtl: self.rtl,
+            });
+
+            let session = &text_view.session;
+            let utf8_len = session.next_grapheme().map_or(1, str::len);
+            let composing = text_view.marked_text.is_some();
+            let head = session.head() + text_view.marked_text.as_ref().map_or(0, String::len);
 
-            // Set scroll target
-            let scrolled_lines = scrolled_lines(text_view.target_scroll, window.line_height());
-            text_view.target_scroll = (glyph_position.y - window.line_height()).max(px(0.0));
+            // Find the glyphs under the head
+            let line_idx = line_starts.partition_point(|&line_start| line_start <= head) - 1;
+            let head_line = &lines[line_idx];
+            let line_head = head - line_starts[line_idx];
+            let head_extent = head_line
+                .glyph_extent(line_head..line_head + utf8_len)
+                .unwrap_or_else(|| {
+                    // Past the last glyph of the line
+                    let line_end = if self.rtl { px(0.0) } else { head_line.width };
+                    line_end..line_end
+                });
+            let reading_x = |line_width: Pixels, extent: Range<Pixels>| {
+                if self.rtl {
+                    line_width - extent.end
+                } else {
+                    extent.start
+                }
+            };
 
             // Update cursor
+            let align_offset = if self.rtl {
+                text_width - head_line.width
+            } else {
+                px(0.0)
+            };
+            let cursor_width = line_height / 3.0;
             let current_cursor = *text_view.cursor.read(cx);
             let new_cursor = Cursor {
-                line_height: window.line_height(),
-                target_position: cursor_position,
+                line_height,
+                target_position: point(
+                    align_offset + (head_extent.start + head_extent.end) / 2.0 - cursor_width / 2.0,
+                    line_height * (line_idx + 1) as f32 - head_line.descent,
+                ),
                 text_origin: scrolled_bounds.origin,
                 animate_movement: text_view.animate_scroll,
             };
@@ -388,30 +1055,83 @@ impl Element for TextViewElement {
                 cx.emit(new_cursor);
             }
 
-            // Remove old text
-            if scrolled_lines != 0 && !self.scrolling {
-                let layout = styled_text.layout().line_layout_for_index(0).unwrap();
-                let wrap_boundary = layout.wrap_boundaries[scrolled_lines - 1];
-                text_view.fruncate_text(
-                    layout.runs()[wrap_boundary.run_ix].glyphs[wrap_boundary.glyph_ix].index,
-                );
-            } else {
-                text_view.animate_scroll = true;
-            }
+            match settings.layout {
+                TextViewLayout::Wrapped { visible_lines } => {
+                    // Lay out again if the width which lines were wrapped to is stale
+                    if text_view.wrap_width != Some(bounds.size.width) {
+                        text_view.wrap_width = Some(bounds.size.width);
+                        window.refresh();
+                    }
+
+                    // Set scroll target
+                    let scrolled_lines = scrolled_lines(text_view.target_scroll.y, line_height);
+                    text_view.target_scroll.y =
+                        line_height * line_idx.saturating_sub(settings.layout.active_line()) as f32;
+
+                    // Remove old text
+                    if let Some(&line_start) = line_starts
+                        .get(scrolled_lines)
+                        .filter(|_| scrolled_lines != 0 && !self.scrolling && !composing)
+                    {
+                        text_view.fruncate_text(
+                            line_start,
+                            point(px(0.0), line_height * scrolled_lines as f32),
+                        );
+                    } else {
+                        text_view.animate_scroll = true;
+                    }
+
+                    // Add new text
+                    let num_full_lines = line_starts.len() - 1;
+                    let lines_ahead = num_full_lines.saturating_sub(scrolled_lines);
+                    if endless && lines_ahead < visible_lines + 2 {
+                        let text = TextView::source_text(&mut text_view.next_drill, 16, cx);
+                        text_view.session.push_text(&format!(" {text}"));
+                    }
+                }
+                TextViewLayout::Tape { caret_position } => {
+                    // Set scroll target
+                    let caret_x = reading_x(head_line.width, head_extent);
+                    text_view.target_scroll.x = caret_x - bounds.size.width * caret_position;
 
-            // Add new text
-            let num_full_lines = styled_text
-                .layout()
-                .line_layout_for_index(0)
-                .unwrap()
-                .wrap_boundaries
-                .len();
-            if num_full_lines - scrolled_lines < 5 {
-                text_view
-                    .text
-                    .push_str(format!(" {}", Dictionary::random_text(16, cx)).as_str());
+                    // Remove words which are fully out of view
+                    let typed = &text_view.session.text()[..text_view.session.head()];
+                    let mut word_starts = BTreeMap::<usize, Range<Pixels>>::new();
+                    for glyph in &head_line.glyphs {
+                        let word_start = typed
+                            .get(..glyph.index)
+                            .is_some_and(|before| before.ends_with(char::is_whitespace));
+                        if word_start {
+                            let extent = word_starts
+                                .entry(glyph.index)
+                                .or_insert_with(|| glyph.extent.clone());
+                            *extent = extent.start.min(glyph.extent.start)
+                                ..extent.end.max(glyph.extent.end);
+                        }
+                    }
+                    let fruncate = word_starts
+                        .into_iter()
+                        .map(|(idx, extent)| (idx, reading_x(head_line.width, extent)))
+                        .take_while(|&(_, x)| x < self.scroll.x)
+                        .last();
+                    if let Some((fruncate_idx, fruncate_x)) =
+                        fruncate.filter(|_| !self.scrolling && !composing)
+                    {
+                        text_view.fruncate_text(fruncate_idx, point(fruncate_x, px(0.0)));
+                    } else {
+                        text_view.animate_scroll = true;
+                    }
+
+                    // Add new text
+                    if endless && head_line.width - caret_x < bounds.size.width * 2.0 {
+                        let text = TextView::source_text(&mut text_view.next_drill, 16, cx);
+                        text_view.session.push_text(&format!(" {text}"));
+                    }
+                }
             }
         });
+
+        scrolled_bounds
     }
 
     fn paint(
@@ -419,61 +1139,105 @@ impl Element for TextViewElement {
         _id: Option<&GlobalElementId>,
         _inspector_id: Option<&InspectorElementId>,
         bounds: Bounds<Pixels>,
-        styled_text: &mut Self::RequestLayoutState,
-        _prepaint: &mut Self::PrepaintState,
+        layout_state: &mut Self::RequestLayoutState,
+        scrolled_bounds: &mut Self::PrepaintState,
         window: &mut Window,
         cx: &mut App,
     ) {
-        styled_text.paint(
-            None,
-            None,
-            bounds - point(px(0.0), self.scroll),
-            &mut (),
-            &mut (),
-            window,
+        let focus_handle = self.entity.read(cx).focus_handle.clone();
+        window.handle_input(
+            &focus_handle,
+            ElementInputHandler::new(bounds, self.entity.clone()),
             cx,
         );
+
+        let line_height = window.line_height();
+        for (line_idx, line) in layout_state.lines.iter().enumerate() {
+            // Right-to-left lines are aligned to the right edge
+            let align_offset = if self.rtl {
+                scrolled_bounds.size.width - line.width
+            } else {
+                px(0.0)
+            };
+            line.paint(
+                scrolled_bounds.origin + point(align_offset, line_height * line_idx as f32),
+                line_height,
+                window,
+            );
+        }
+    }
+}
+
+/// Picks the byte offsets at which lines start so that none are wider than `wrap_width`. Lines
+/// break after whitespace, which is kept at the end of the line, using the same rule for
+/// whitespace as the typing session.
+fn line_starts(
+    text: &str,
+    wrap_width: Option<Pixels>,
+    mut measure: impl FnMut(&str) -> Pixels,
+) -> Vec<usize> {
+    let mut line_starts = vec![0];
+    let mut line_width = px(0.0);
+    let mut offset = 0;
+
+    for word in text.split_inclusive(char::is_whitespace) {
+        if let Some(wrap_width) = wrap_width {
+            let trimmed = word.trim_end_matches(char::is_whitespace);
+            let word_width = measure(trimmed);
+            if line_starts.last() != Some(&offset) && line_width + word_width > wrap_width {
+                line_starts.push(offset);
+                line_width = px(0.0);
+            }
+            line_width += word_width + measure(&word[trimmed.len()..]);
+        }
+
+        offset += word.len();
+        if word.ends_with('\n') {
+            line_starts.push(offset);
+            line_width = px(0.0);
+        }
     }
+
+    line_starts
 }
 
-fn cursor_pos(
-    utf8_idx: usize,
-    utf8_len: usize,
-    layout: &TextLayout,
-    cursor_width: Pixels,
-) -> (Point<Pixels>, Point<Pixels>) {
-    let line_height = layout.line_height();
-    let layout = layout.line_layout_for_index(utf8_idx).unwrap();
-
-    // Get glyph position and width
-    let glyph_width = layout.unwrapped_layout.x_for_index(utf8_idx + utf8_len)
-        - layout.unwrapped_layout.x_for_index(utf8_idx);
-
-    let glyph_position = layout.position_for_index(utf8_idx, line_height).unwrap();
-    let glyph_position = if layout
-        .wrap_boundaries()
-        .iter()
-        .map(|wrap_boundary| {
-            layout.runs()[wrap_boundary.run_ix].glyphs[wrap_boundary.glyph_ix].index
+/// Styles for `range` of the text, starting from the start of the range
+fn line_runs(runs: &[TextRun], range: Range<usize>) -> Vec<TextRun> {
+    let mut run_start = 0;
+    runs.iter()
+        .filter_map(|run| {
+            let start = run_start.max(range.start);
+            let end = (run_start + run.len).min(range.end);
+            run_start += run.len;
+            (start < end).then(|| {
+                TextRun {
+                    len: end - start,
+                    ..run.clone()
+                }
+            })
         })
-        .any(|wrap_idx| wrap_idx == utf8_idx)
-    {
-        // Go to next line
-        point(px(0.0), glyph_position.y + line_height)
-    } else {
-        glyph_position
-    };
-
-    // Calculate cursor x position
-    let cursor_center_x = glyph_position.x + glyph_width / 2.0;
-    let cursor_x = cursor_center_x - cursor_width / 2.0;
-
-    // Calculate cursor y position
-    let cursor_y = glyph_position.y + line_height - layout.descent();
-
-    (glyph_position, point(cursor_x, cursor_y))
+        .collect()
 }
 
 fn scrolled_lines(y_pos: Pixels, line_height: Pixels) -> usize {
     (y_pos / line_height + 0.5) as usize
 }
+
+fn range_from_utf16(text: &str, range_utf16: &Range<usize>) -> Range<usize> {
+    offset_from_utf16(text, range_utf16.start)..offset_from_utf16(text, range_utf16.end)
+}
+
+fn range_to_utf16(text: &str, range: &Range<usize>) -> Range<usize> {
+    text[..range.start].encode_utf16().count()..text[..range.end].encode_utf16().count()
+}
+
+fn offset_from_utf16(text: &str, offset_utf16: usize) -> usize {
+    let mut utf16_count = 0;
+    for (idx, char) in text.char_indices() {
+        if utf16_count >= offset_utf16 {
+            return idx;
+        }
+        utf16_count += char.len_utf16();
+    }
+    text.len()
+}
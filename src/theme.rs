@@ -117,12 +117,38 @@ pub struct Theme {
     pub counter_idle_message: &'static str,
     pub counter_idle_text: Rgba,
     pub counter_font_family: &'static str,
+    pub counter_preset_selected_text: Rgba,
+    pub counter_preset_text: Rgba,
     pub counter_text: Rgba,
     #[cfg(not(target_family = "wasm"))]
     pub csd: CsdTheme,
+    pub keyboard_heatmap_cold: Rgba,
+    pub keyboard_heatmap_hot: Rgba,
+    pub keyboard_hint_finger: Rgba,
+    pub keyboard_hint_key: Rgba,
+    pub keyboard_key_background: Rgba,
+    pub keyboard_key_text: Rgba,
+    pub results_label_text: Rgba,
+    pub results_net_wpm_line: Rgba,
+    pub results_raw_wpm_line: Rgba,
+    pub results_value_text: Rgba,
+    pub steno_hint_text: Rgba,
+    pub steno_machine_error: Rgba,
+    pub steno_tape_font_family: &'static str,
+    pub steno_tape_stroke: Rgba,
+    pub steno_tape_suggestion: Rgba,
+    pub steno_tape_translation: Rgba,
+    pub steno_tape_untranslated: Rgba,
+    pub text_view_active_word_background: Rgba,
+    pub text_view_blind_text: Rgba,
     pub text_view_correct_text: Rgba,
     pub text_view_cursor: Rgba,
+    pub text_view_error_underline: Rgba,
+    pub text_view_error_underline_wavy: bool,
+    pub text_view_extra_text: Rgba,
     pub text_view_incorrect_text: Rgba,
+    pub text_view_marked_text: Rgba,
+    pub text_view_missed_text: Rgba,
     pub text_view_placeholder_text: Rgba,
     pub window_background: Rgba,
 }
@@ -130,7 +156,17 @@ pub struct Theme {
 impl Theme {
     pub fn default_light() -> Self {
         Self {
+            keyboard_heatmap_hot: rgb(0xf44336),
+            keyboard_hint_finger: rgba(0x0288d130),
+            keyboard_hint_key: rgba(0x0288d1c0),
+            keyboard_key_background: rgba(0x00000010),
+            steno_machine_error: rgb(0xf44336),
+            steno_tape_untranslated: rgb(0xf44336),
+            text_view_active_word_background: rgba(0x0000000c),
+            text_view_error_underline: rgba(0xf44336a0),
+            text_view_extra_text: rgb(0xb71c1c),
             text_view_incorrect_text: rgb(0xf44336),
+            text_view_missed_text: rgba(0xf4433680),
             ..Self::from(BaseTheme::default_light())
         }
     }
@@ -189,12 +225,38 @@ impl From<BaseTheme> for Theme {
             counter_idle_message: "Type to start...",
             counter_idle_text: base.dim_foreground,
             counter_font_family: base.font_family,
+            counter_preset_selected_text: base.foreground,
+            counter_preset_text: base.dim_foreground,
             counter_text: base.foreground,
             #[cfg(not(target_family = "wasm"))]
             csd: CsdTheme::from(base),
+            keyboard_heatmap_cold: rgba(0x43a04780),
+            keyboard_heatmap_hot: rgb(0xe23636),
+            keyboard_hint_finger: rgba(0x4fc3f730),
+            keyboard_hint_key: rgba(0x4fc3f7a0),
+            keyboard_key_background: rgba(0xffffff10),
+            keyboard_key_text: base.foreground,
+            results_label_text: base.dim_foreground,
+            results_net_wpm_line: base.foreground,
+            results_raw_wpm_line: base.dim_foreground,
+            results_value_text: base.foreground,
+            steno_hint_text: base.dim_foreground,
+            steno_machine_error: rgb(0xe23636),
+            steno_tape_font_family: "Monospace",
+            steno_tape_stroke: base.foreground,
+            steno_tape_suggestion: base.dim_foreground,
+            steno_tape_translation: base.dim_foreground,
+            steno_tape_untranslated: rgb(0xe23636),
+            text_view_active_word_background: rgba(0xffffff0c),
+            text_view_blind_text: base.foreground,
             text_view_correct_text: base.foreground,
             text_view_cursor: base.foreground,
+            text_view_error_underline: rgba(0xe23636a0),
+            text_view_error_underline_wavy: true,
+            text_view_extra_text: rgb(0x9c2020),
             text_view_incorrect_text: rgb(0xe23636),
+            text_view_marked_text: base.foreground,
+            text_view_missed_text: rgba(0xe2363660),
             text_view_placeholder_text: base.dim_foreground,
             window_background: base.background,
         }
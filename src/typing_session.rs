@@ -0,0 +1,1184 @@
+use std::mem;
+use std::ops::Range;
+use std::time::Duration;
+
+use unicode_segmentation::UnicodeSegmentation;
+use web_time::Instant;
+
+const WPM_CHARS_PER_WORD: f32 = 5.0;
+
+/// Input which can be applied to a [`TypingSession`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Input {
+    Backspace,
+    Text(String),
+    /// A steno stroke, which removes everything typed by the latest `undo` texts which haven't
+    /// been removed and then types each of `texts`. It counts as a single keystroke however much
+    /// text it rewrites.
+    Stroke {
+        undo: usize,
+        texts: Vec<String>,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RunKind {
+    Correct,
+    /// A character substituted for the expected one
+    Incorrect,
+    /// A character typed past the end of a word
+    Extra,
+    /// A character skipped by ending the word early
+    Missed,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct CharCounts {
+    pub correct: usize,
+    pub incorrect: usize,
+    pub extra: usize,
+    pub missed: usize,
+}
+
+impl CharCounts {
+    fn get_mut(&mut self, kind: RunKind) -> &mut usize {
+        match kind {
+            RunKind::Correct => &mut self.correct,
+            RunKind::Incorrect => &mut self.incorrect,
+            RunKind::Extra => &mut self.extra,
+            RunKind::Missed => &mut self.missed,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct SessionStats {
+    /// Every grapheme typed, every backspace and every steno stroke
+    pub keystrokes: usize,
+    /// Graphemes typed which matched the target text
+    pub correct_keystrokes: usize,
+    /// Graphemes typed which were substituted, extra, skipped words early or were ignored
+    pub incorrect_keystrokes: usize,
+    /// Incorrect and extra characters which were deleted
+    pub corrected_errors: usize,
+    /// Words which were completed without any mistakes remaining
+    pub correct_words: usize,
+    /// Characters of each kind which are currently typed
+    pub char_counts: CharCounts,
+}
+
+impl SessionStats {
+    /// Mistakes which remain in the typed text
+    pub fn uncorrected_errors(&self) -> usize {
+        self.char_counts.incorrect + self.char_counts.extra + self.char_counts.missed
+    }
+
+    /// Percentage of typed graphemes which were correct
+    pub fn accuracy(&self) -> f32 {
+        let typed = self.correct_keystrokes + self.incorrect_keystrokes;
+        if typed == 0 {
+            return 0.0;
+        }
+
+        self.correct_keystrokes as f32 / typed as f32 * 100.0
+    }
+
+    /// Words per minute counting every grapheme typed
+    pub fn raw_wpm(&self, elapsed: Duration) -> f32 {
+        wpm(self.correct_keystrokes + self.incorrect_keystrokes, elapsed)
+    }
+
+    /// Words per minute counting every grapheme typed, less the mistakes which remain
+    pub fn net_wpm(&self, elapsed: Duration) -> f32 {
+        wpm(
+            (self.correct_keystrokes + self.incorrect_keystrokes)
+                .saturating_sub(self.uncorrected_errors()),
+            elapsed,
+        )
+    }
+}
+
+/// How a word was typed, recorded when moving on to the next word
+#[derive(Clone, Debug, PartialEq)]
+pub struct WordRecord {
+    /// The word as it appeared in the target text
+    pub target: String,
+    pub typed: String,
+    /// The word as it was first typed, keeping the graphemes which were later corrected
+    pub first_typed: String,
+    /// When the first grapheme of the word was typed
+    pub start: Instant,
+    pub duration: Duration,
+    /// Incorrect keystrokes made in the word, including ones which were later corrected
+    pub mistakes: usize,
+    typed_keystrokes: usize,
+    first_typed_keystrokes: usize,
+}
+
+impl WordRecord {
+    /// Speed the word was typed at. Timing starts at the word's first keystroke, so this counts
+    /// the rest of the word and the whitespace which finished it.
+    pub fn wpm(&self) -> f32 {
+        wpm(self.target.graphemes(true).count(), self.duration)
+    }
+}
+
+/// A single keystroke, kept in a log for analysing it after the session
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeystrokeRecord {
+    /// Grapheme which was typed, or `None` for a backspace
+    pub typed: Option<String>,
+    /// Target grapheme at the head when the key was pressed
+    pub expected: Option<String>,
+    pub time: Instant,
+    pub correct: bool,
+}
+
+/// Progress through the word being typed, which becomes a [`WordRecord`] once it is finished
+#[derive(Default)]
+struct WordProgress {
+    typed: String,
+    first_typed: String,
+    /// Keystrokes which are currently typed into the word
+    typed_keystrokes: usize,
+    /// Positions in the word which have been typed at least once
+    first_typed_keystrokes: usize,
+    start: Option<Instant>,
+    mistakes: usize,
+}
+
+/// Text added to the runs by a single keystroke, so that a backspace removes exactly what it added
+/// even when it joined into the grapheme before it
+#[derive(Clone, Copy)]
+struct Keystroke {
+    /// Length of the run text covered by the keystroke
+    utf8_len: usize,
+    /// Length of the text it added to the word being typed
+    typed_len: usize,
+}
+
+/// Typing state for a target text, independent of how it is displayed
+///
+/// Typed characters replace the target text in place, except for over-inserted characters which
+/// are inserted into it. The text before the head is covered by runs describing how each
+/// character was typed.
+pub struct TypingSession {
+    text: String,
+    utf8_head: usize,
+    over_inserted_stack: Vec<usize>,
+    run_lens: Vec<(RunKind, usize)>,
+    stats: SessionStats,
+    words: Vec<WordRecord>,
+    word_progress: WordProgress,
+    keystroke_log: Vec<KeystrokeRecord>,
+    /// Keystrokes which the runs are made of, in order
+    keystrokes: Vec<Keystroke>,
+    /// Head before each `Text` input, for undoing them
+    undo_stack: Vec<usize>,
+}
+
+impl TypingSession {
+    pub fn new(text: String) -> Self {
+        Self {
+            text,
+            utf8_head: 0,
+            over_inserted_stack: vec![0],
+            run_lens: Vec::new(),
+            stats: SessionStats::default(),
+            words: Vec::new(),
+            word_progress: WordProgress::default(),
+            keystroke_log: Vec::new(),
+            keystrokes: Vec::new(),
+            undo_stack: Vec::new(),
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Byte offset of the next character to be typed
+    pub fn head(&self) -> usize {
+        self.utf8_head
+    }
+
+    pub fn stats(&self) -> SessionStats {
+        self.stats
+    }
+
+    /// Words which have been finished, including ones which were fruncated
+    pub fn words(&self) -> &[WordRecord] {
+        &self.words
+    }
+
+    /// Every keystroke in the order they were pressed
+    pub fn keystroke_log(&self) -> &[KeystrokeRecord] {
+        &self.keystroke_log
+    }
+
+    /// Target grapheme at the head
+    pub fn next_grapheme(&self) -> Option<&str> {
+        self.text[self.utf8_head..].graphemes(true).next()
+    }
+
+    /// Appends more target text
+    pub fn push_text(&mut self, text: &str) {
+        self.text.push_str(text);
+    }
+
+    /// Byte ranges and kinds of each run
+    pub fn runs(&self) -> impl Iterator<Item = (RunKind, Range<usize>)> + '_ {
+        self.run_lens.iter().scan(0, |offset, &(kind, run_len)| {
+            let start = *offset;
+            *offset += run_len;
+            Some((kind, start..*offset))
+        })
+    }
+
+    /// Range of the word the head is currently in, including untyped characters
+    pub fn active_word(&self) -> Range<usize> {
+        let start = self.text[..self.utf8_head]
+            .char_indices()
+            .rfind(|(_, char)| char.is_whitespace())
+            .map_or(0, |(idx, char)| idx + char.len_utf8());
+        let end = self.text[self.utf8_head..]
+            .find(char::is_whitespace)
+            .map_or(self.text.len(), |idx| self.utf8_head + idx);
+
+        start..end
+    }
+
+    /// Ranges of the whitespace separated words within `range`
+    pub fn word_ranges(&self, range: Range<usize>) -> impl Iterator<Item = Range<usize>> + '_ {
+        self.text[range.clone()]
+            .split_inclusive(char::is_whitespace)
+            .scan(range.start, |offset, word| {
+                let start = *offset;
+                *offset += word.len();
+                Some(start..start + word.trim_end().len())
+            })
+    }
+
+    pub fn handle_input(&mut self, input: Input) {
+        self.handle_input_at(input, Instant::now());
+    }
+
+    /// Handles input which was received at `time`
+    pub fn handle_input_at(&mut self, input: Input, time: Instant) {
+        match input {
+            Input::Backspace => {
+                self.stats.keystrokes += 1;
+                self.keystroke_log.push(KeystrokeRecord {
+                    typed: None,
+                    expected: None,
+                    time,
+                    correct: false,
+                });
+                self.backspace();
+            }
+            Input::Text(text) => {
+                self.stats.keystrokes += text.graphemes(true).count();
+                self.type_text(&text, time);
+            }
+            Input::Stroke { undo, texts } => {
+                self.stats.keystrokes += 1;
+                for _ in 0..undo {
+                    self.undo();
+                }
+                for text in texts {
+                    self.type_text(&text, time);
+                }
+            }
+        }
+    }
+
+    /// Types each grapheme of `text`, which can later be removed together by an undo
+    fn type_text(&mut self, text: &str, time: Instant) {
+        self.undo_stack.push(self.utf8_head);
+        for grapheme in text.graphemes(true) {
+            let expected = self.next_grapheme().map(str::to_string);
+            let correct_keystrokes = self.stats.correct_keystrokes;
+            self.type_grapheme(grapheme, time);
+            self.keystroke_log.push(KeystrokeRecord {
+                typed: Some(grapheme.to_string()),
+                expected,
+                time,
+                correct: self.stats.correct_keystrokes > correct_keystrokes,
+            });
+        }
+    }
+
+    /// Whether the head is at the end of the active word, with all of it typed correctly
+    fn active_word_correct(&self) -> bool {
+        let word = self.active_word();
+        !word.is_empty()
+            && self.utf8_head == word.end
+            && self
+                .runs()
+                .filter(|(_, run)| run.end > word.start)
+                .all(|(kind, _)| kind == RunKind::Correct)
+    }
+
+    fn type_grapheme(&mut self, typed: &str, time: Instant) {
+        if is_whitespace(typed) {
+            let after_word = self.text[..self.utf8_head]
+                .chars()
+                .next_back()
+                .is_some_and(|char| !char.is_whitespace());
+            if !((after_word || self.utf8_head == 0) && self.add_whitespace(typed, time)) {
+                self.stats.incorrect_keystrokes += 1;
+            }
+            return;
+        }
+
+        let replaced = self
+            .next_grapheme()
+            .filter(|replaced| !is_whitespace(replaced))
+            .map(|replaced| (replaced.len(), replaced == typed));
+        let progress = &mut self.word_progress;
+        progress.start.get_or_insert(time);
+        progress.typed.push_str(typed);
+        // Only the first grapheme typed at each position is kept
+        if progress.typed_keystrokes == progress.first_typed_keystrokes {
+            progress.first_typed.push_str(typed);
+            progress.first_typed_keystrokes += 1;
+        }
+        progress.typed_keystrokes += 1;
+        if replaced.is_none_or(|(_, correct)| !correct) {
+            progress.mistakes += 1;
+        }
+
+        match replaced {
+            Some((replaced_len, correct)) => {
+                let kind = if correct {
+                    self.stats.correct_keystrokes += 1;
+                    RunKind::Correct
+                } else {
+                    self.stats.incorrect_keystrokes += 1;
+                    RunKind::Incorrect
+                };
+                self.add_run(kind, replaced_len, typed.len());
+            }
+            None => {
+                self.stats.incorrect_keystrokes += 1;
+                self.text.insert_str(self.utf8_head, typed);
+                self.add_run(RunKind::Extra, typed.len(), typed.len());
+                *self.over_inserted_stack.last_mut().unwrap() += typed.len();
+            }
+        }
+    }
+
+    /// Adds a keystroke covering `utf8_len` bytes from the head, which added `typed_len` bytes to
+    /// the word being typed
+    fn add_run(&mut self, kind: RunKind, utf8_len: usize, typed_len: usize) {
+        let run_start = match self.runs().last() {
+            Some((last_run_kind, last_run)) if last_run_kind == kind => last_run.start,
+            _ => self.utf8_head,
+        };
+        // The added text may join into the last grapheme of the run
+        *self.stats.char_counts.get_mut(kind) += graphemes_after(
+            &self.text[run_start..self.utf8_head + utf8_len],
+            self.utf8_head - run_start,
+        );
+
+        if let Some((last_run_kind, last_run)) = self.run_lens.last_mut() {
+            if *last_run_kind == kind {
+                *last_run += utf8_len;
+            } else {
+                self.run_lens.push((kind, utf8_len));
+            }
+        } else {
+            self.run_lens.push((kind, utf8_len));
+        }
+        self.keystrokes.push(Keystroke {
+            utf8_len,
+            typed_len,
+        });
+
+        self.utf8_head += utf8_len;
+    }
+
+    /// Moves to the next word, returning whether there was one
+    fn add_whitespace(&mut self, whitespace: &str, time: Instant) -> bool {
+        let end_of_word = self.active_word().end;
+        let replace_len: usize = self.text[end_of_word..]
+            .chars()
+            .take_while(|char| char.is_whitespace())
+            .map(char::len_utf8)
+            .sum();
+        if replace_len == 0 {
+            // There is no next word to move to
+            return false;
+        }
+
+        if self.active_word_correct() {
+            self.stats.correct_words += 1;
+        }
+
+        // Add run for skipped chars
+        let skipped = end_of_word > self.utf8_head;
+        if skipped {
+            // Each skipped grapheme is unwound by its own backspace
+            while self.utf8_head < end_of_word {
+                let missed_len = self.next_grapheme().map_or(0, str::len);
+                self.add_run(RunKind::Missed, missed_len, 0);
+            }
+            self.word_progress.mistakes += 1;
+        }
+
+        // Record the finished word
+        let word_start = self.active_word().start;
+        let target = self
+            .runs()
+            .filter(|(kind, run)| *kind != RunKind::Extra && run.end > word_start)
+            .map(|(_, run)| &self.text[run.start.max(word_start)..run.end])
+            .collect::<String>();
+        let progress = mem::take(&mut self.word_progress);
+        let start = progress.start.unwrap_or(time);
+        self.words.push(WordRecord {
+            target,
+            typed: progress.typed,
+            first_typed: progress.first_typed,
+            start,
+            duration: time.saturating_duration_since(start),
+            mistakes: progress.mistakes,
+            typed_keystrokes: progress.typed_keystrokes,
+            first_typed_keystrokes: progress.first_typed_keystrokes,
+        });
+
+        // Replace whitespace with written whitespace
+        self.text
+            .replace_range(end_of_word..end_of_word + replace_len, whitespace);
+
+        // Add run for whitespace
+        self.add_run(RunKind::Correct, whitespace.len(), 0);
+
+        // Advance over inserted stack
+        self.over_inserted_stack.push(0);
+
+        if skipped {
+            self.stats.incorrect_keystrokes += 1;
+        } else {
+            self.stats.correct_keystrokes += 1;
+        }
+        true
+    }
+
+    fn backspace(&mut self) {
+        // Unwind the latest keystroke, which is always within the last run
+        let Some(unwound) = self.keystrokes.pop() else {
+            return;
+        };
+        let unwind_start = self.utf8_head - unwound.utf8_len;
+        let unwound_whitespace = is_whitespace(&self.text[unwind_start..self.utf8_head]);
+
+        // Remove runs
+        let (last_run_kind, last_run) = self.runs().last().unwrap();
+        *self.stats.char_counts.get_mut(last_run_kind) -= graphemes_after(
+            &self.text[last_run.start..self.utf8_head],
+            unwind_start - last_run.start,
+        );
+        if matches!(last_run_kind, RunKind::Incorrect | RunKind::Extra) {
+            self.stats.corrected_errors += 1;
+        }
+        let last_run_len = &mut self.run_lens.last_mut().unwrap().1;
+        *last_run_len -= unwound.utf8_len;
+        if *last_run_len == 0 {
+            self.run_lens.pop();
+        }
+
+        // Remove text
+        let over_inserted = self.over_inserted_stack.last_mut().unwrap();
+        if *over_inserted != 0 {
+            self.text.replace_range(unwind_start..self.utf8_head, "");
+            *over_inserted -= unwound.utf8_len;
+        }
+
+        self.utf8_head = unwind_start;
+
+        if unwound.typed_len != 0 {
+            let progress = &mut self.word_progress;
+            progress
+                .typed
+                .truncate(progress.typed.len() - unwound.typed_len);
+            progress.typed_keystrokes -= 1;
+        }
+
+        // Move back into the previous word
+        if unwound_whitespace && self.over_inserted_stack.len() > 1 {
+            self.over_inserted_stack.pop();
+            if self.active_word_correct() {
+                self.stats.correct_words -= 1;
+            }
+            if let Some(word) = self.words.pop() {
+                self.word_progress = WordProgress {
+                    typed: word.typed,
+                    first_typed: word.first_typed,
+                    typed_keystrokes: word.typed_keystrokes,
+                    first_typed_keystrokes: word.first_typed_keystrokes,
+                    start: Some(word.start),
+                    mistakes: word.mistakes,
+                };
+            }
+        }
+    }
+
+    fn undo(&mut self) {
+        // Skip inputs which typed nothing or were already removed by backspaces
+        while let Some(start) = self.undo_stack.pop() {
+            if start >= self.utf8_head {
+                continue;
+            }
+
+            while self.utf8_head > start {
+                let head = self.utf8_head;
+                self.backspace();
+                if self.utf8_head == head {
+                    break;
+                }
+            }
+            return;
+        }
+    }
+
+    /// Removes the first `utf8_len` bytes of text, which must end at a word boundary before the
+    /// head
+    pub fn fruncate(&mut self, utf8_len: usize) {
+        // Fruncate runs
+        let first_kept_run = self
+            .runs()
+            .enumerate()
+            .find(|(_, (_, run))| run.end > utf8_len)
+            .map(|(run_idx, (_, run))| (run_idx, run.end));
+        if let Some((run_idx, run_end)) = first_kept_run {
+            self.run_lens.drain(0..run_idx);
+            self.run_lens[0].1 = run_end - utf8_len;
+        } else {
+            self.run_lens.clear();
+        }
+
+        // Remove stack entries
+        let words_completed = self.text[..utf8_len]
+            .chars()
+            .filter(|char| char.is_whitespace())
+            .count();
+        self.over_inserted_stack.drain(0..words_completed);
+        let mut keystrokes_len = 0;
+        let keystrokes_fruncated = self
+            .keystrokes
+            .iter()
+            .take_while(|keystroke| {
+                keystrokes_len += keystroke.utf8_len;
+                keystrokes_len <= utf8_len
+            })
+            .count();
+        self.keystrokes.drain(0..keystrokes_fruncated);
+        self.undo_stack.retain(|&start| start >= utf8_len);
+        for start in &mut self.undo_stack {
+            *start -= utf8_len;
+        }
+
+        // Remove text
+        self.text.drain(0..utf8_len);
+
+        // Move head
+        self.utf8_head -= utf8_len;
+    }
+}
+
+/// Score out of 100 for how steady a series of speeds is, from their coefficient of variation
+pub fn consistency(speeds: &[f32]) -> f32 {
+    let mean = speeds.iter().sum::<f32>() / speeds.len() as f32;
+    if speeds.is_empty() || mean <= 0.0 {
+        return 0.0;
+    }
+
+    let variance = speeds
+        .iter()
+        .map(|speed| (speed - mean).powi(2))
+        .sum::<f32>()
+        / speeds.len() as f32;
+    (1.0 - (variance.sqrt() / mean).tanh()) * 100.0
+}
+
+pub fn wpm(chars: usize, elapsed: Duration) -> f32 {
+    if elapsed.is_zero() {
+        return 0.0;
+    }
+
+    chars as f32 / WPM_CHARS_PER_WORD / (elapsed.as_secs_f32() / 60.0)
+}
+
+fn is_whitespace(grapheme: &str) -> bool {
+    grapheme.chars().all(char::is_whitespace)
+}
+
+/// Number of graphemes in `text` which aren't in `text[..split]`, where the text after `split` may
+/// join into the last grapheme before it
+fn graphemes_after(text: &str, split: usize) -> usize {
+    // Segmenting in reverse can join graphemes which are separate when segmented forwards, such as
+    // consecutive Indic conjuncts
+    let Some(last_start) = text[..split]
+        .grapheme_indices(true)
+        .map(|(idx, _)| idx)
+        .max()
+    else {
+        return text.graphemes(true).count();
+    };
+
+    text[last_start..].graphemes(true).count() - 1
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn session(text: &str, typed: &str) -> TypingSession {
+        let mut session = TypingSession::new(text.to_string());
+        session.handle_input(Input::Text(typed.to_string()));
+        session
+    }
+
+    fn runs(session: &TypingSession) -> Vec<(RunKind, &str)> {
+        session
+            .runs()
+            .map(|(kind, run)| (kind, &session.text()[run]))
+            .collect()
+    }
+
+    #[test]
+    fn correct_text() {
+        let session = session("hello world", "hello w");
+        assert_eq!(runs(&session), [(RunKind::Correct, "hello w")]);
+        assert_eq!(session.head(), 7);
+        assert_eq!(session.stats().correct_keystrokes, 7);
+        assert_eq!(session.stats().char_counts.correct, 7);
+    }
+
+    #[test]
+    fn substituted_char() {
+        let session = session("hello world", "hallo");
+        assert_eq!(
+            runs(&session),
+            [
+                (RunKind::Correct, "h"),
+                (RunKind::Incorrect, "e"),
+                (RunKind::Correct, "llo"),
+            ]
+        );
+        assert_eq!(session.text(), "hello world");
+        assert_eq!(session.stats().correct_keystrokes, 4);
+    }
+
+    #[test]
+    fn extra_chars() {
+        let session = session("hi there", "hiya t");
+        assert_eq!(session.text(), "hiya there");
+        assert_eq!(
+            runs(&session),
+            [
+                (RunKind::Correct, "hi"),
+                (RunKind::Extra, "ya"),
+                (RunKind::Correct, " t"),
+            ]
+        );
+        assert_eq!(session.stats().char_counts.extra, 2);
+    }
+
+    #[test]
+    fn missed_chars() {
+        let session = session("hello world", "he w");
+        assert_eq!(
+            runs(&session),
+            [
+                (RunKind::Correct, "he"),
+                (RunKind::Missed, "llo"),
+                (RunKind::Correct, " w"),
+            ]
+        );
+        assert_eq!(session.stats().char_counts.missed, 3);
+    }
+
+    #[test]
+    fn repeated_whitespace_is_ignored() {
+        let session = session("a b", "a   ");
+        assert_eq!(session.head(), 2);
+        assert_eq!(session.active_word(), 2..3);
+    }
+
+    #[test]
+    fn whitespace_at_end_of_text_is_ignored() {
+        let session = session("a b", "a b ");
+        assert_eq!(session.head(), 3);
+        assert_eq!(session.text(), "a b");
+    }
+
+    #[test]
+    fn backspace_removes_extra_chars() {
+        let mut session = session("ab cd", "abxy");
+        session.handle_input(Input::Backspace);
+        assert_eq!(session.text(), "abx cd");
+        assert_eq!(
+            runs(&session),
+            [(RunKind::Correct, "ab"), (RunKind::Extra, "x")]
+        );
+        session.handle_input(Input::Backspace);
+        session.handle_input(Input::Backspace);
+        assert_eq!(session.text(), "ab cd");
+        assert_eq!(runs(&session), [(RunKind::Correct, "a")]);
+    }
+
+    #[test]
+    fn backspace_into_previous_word() {
+        let mut session = session("ab cd", "abx c");
+        session.handle_input(Input::Backspace);
+        session.handle_input(Input::Backspace);
+        assert_eq!(session.head(), 3);
+        session.handle_input(Input::Backspace);
+        assert_eq!(session.text(), "ab cd");
+        assert_eq!(session.head(), 2);
+    }
+
+    #[test]
+    fn backspace_at_start_does_nothing() {
+        let mut session = session("ab", "");
+        session.handle_input(Input::Backspace);
+        assert_eq!(session.head(), 0);
+        assert_eq!(session.runs().count(), 0);
+    }
+
+    #[test]
+    fn multi_byte_chars() {
+        let mut session = session("héllo wörld", "héllo wo");
+        assert_eq!(
+            runs(&session),
+            [(RunKind::Correct, "héllo w"), (RunKind::Incorrect, "ö")]
+        );
+        assert_eq!(session.head(), "héllo wö".len());
+        session.handle_input(Input::Backspace);
+        assert_eq!(session.head(), "héllo w".len());
+    }
+
+    #[test]
+    fn combining_accents() {
+        let mut session = session("cafe\u{301} au", "cafe");
+        assert_eq!(
+            runs(&session),
+            [(RunKind::Correct, "caf"), (RunKind::Incorrect, "e\u{301}")]
+        );
+        assert_eq!(session.next_grapheme(), Some(" "));
+
+        session.handle_input(Input::Backspace);
+        session.handle_input(Input::Text("e\u{301}".to_string()));
+        assert_eq!(runs(&session), [(RunKind::Correct, "cafe\u{301}")]);
+        assert_eq!(session.stats().char_counts.correct, 4);
+    }
+
+    #[test]
+    fn emoji_with_modifiers() {
+        let mut session = session("👍🏽 👨‍👩‍👧", "👍🏽 👨‍👩‍👧");
+        assert_eq!(runs(&session), [(RunKind::Correct, "👍🏽 👨‍👩‍👧")]);
+        assert_eq!(session.stats().correct_keystrokes, 3);
+
+        session.handle_input(Input::Backspace);
+        assert_eq!(session.head(), "👍🏽 ".len());
+    }
+
+    #[test]
+    fn indic_conjuncts() {
+        let mut session = session("क्षमा", "क");
+        assert_eq!(runs(&session), [(RunKind::Incorrect, "क्ष")]);
+        assert_eq!(session.next_grapheme(), Some("मा"));
+
+        session.handle_input(Input::Backspace);
+        session.handle_input(Input::Text("क्षमा".to_string()));
+        assert_eq!(runs(&session), [(RunKind::Correct, "क्षमा")]);
+    }
+
+    #[test]
+    fn extra_grapheme_merging_with_previous_run() {
+        let mut session = session("ab cd", "ab");
+        session.handle_input(Input::Text("\u{301}".to_string()));
+        assert_eq!(session.text(), "ab\u{301} cd");
+        assert_eq!(
+            runs(&session),
+            [(RunKind::Correct, "ab"), (RunKind::Extra, "\u{301}")]
+        );
+
+        session.handle_input(Input::Backspace);
+        assert_eq!(session.text(), "ab cd");
+        assert_eq!(runs(&session), [(RunKind::Correct, "ab")]);
+    }
+
+    #[test]
+    fn extra_indic_conjuncts() {
+        let mut session = session("a", "a");
+        session.handle_input(Input::Text("क्ष".to_string()));
+        session.handle_input(Input::Text("क्ष".to_string()));
+        assert_eq!(session.stats().char_counts.extra, 2);
+
+        session.handle_input(Input::Backspace);
+        assert_eq!(session.text(), "aक्ष");
+        assert_eq!(session.stats().char_counts.extra, 1);
+        session.handle_input(Input::Backspace);
+        assert_eq!(session.text(), "a");
+        assert_eq!(session.stats().char_counts.extra, 0);
+    }
+
+    #[test]
+    fn typed_graphemes_joining() {
+        let mut session = session("ab cd", "e");
+        session.handle_input(Input::Text("\u{301}".to_string()));
+        assert_eq!(session.stats().char_counts.incorrect, 2);
+        session.handle_input(Input::Backspace);
+        session.handle_input(Input::Text("b ".to_string()));
+        assert_eq!(session.words()[0].typed, "eb");
+    }
+
+    #[test]
+    fn active_word() {
+        let mut session = session("one two three", "one tw");
+        assert_eq!(session.active_word(), 4..7);
+        session.handle_input(Input::Text("o".to_string()));
+        assert_eq!(session.active_word(), 4..7);
+        session.handle_input(Input::Text(" ".to_string()));
+        assert_eq!(session.active_word(), 8..13);
+    }
+
+    #[test]
+    fn word_ranges() {
+        let session = session("one two three", "");
+        assert_eq!(session.word_ranges(0..8).collect::<Vec<_>>(), [0..3, 4..7]);
+    }
+
+    #[test]
+    fn fruncate() {
+        let mut session = session("one two three", "onx two th");
+        session.fruncate(8);
+        assert_eq!(session.text(), "three");
+        assert_eq!(session.head(), 2);
+        assert_eq!(runs(&session), [(RunKind::Correct, "th")]);
+
+        // Stats are kept
+        assert_eq!(session.stats().char_counts.incorrect, 1);
+    }
+
+    #[test]
+    fn fruncate_at_head() {
+        let mut session = session("one two", "one ");
+        session.fruncate(4);
+        assert_eq!(session.text(), "two");
+        assert_eq!(session.head(), 0);
+        assert_eq!(session.runs().count(), 0);
+    }
+
+    const GRAPHEMES: [&str; 7] = ["a", "b", "c", "é", "e\u{301}", "👍🏽", "क्ष"];
+
+    #[test]
+    fn keystroke_stats() {
+        let stats = session("ab cd ef", "xb  c ef").stats();
+        assert_eq!(stats.keystrokes, 8);
+        assert_eq!(stats.correct_keystrokes, 5);
+        assert_eq!(stats.incorrect_keystrokes, 3);
+        assert_eq!(stats.uncorrected_errors(), 2);
+        assert_eq!(stats.accuracy(), 5.0 / 8.0 * 100.0);
+    }
+
+    #[test]
+    fn corrected_errors() {
+        let mut session = session("ab", "xyz");
+        session.handle_input(Input::Backspace);
+        session.handle_input(Input::Backspace);
+        session.handle_input(Input::Backspace);
+        session.handle_input(Input::Text("ab".to_string()));
+
+        let stats = session.stats();
+        assert_eq!(stats.corrected_errors, 3);
+        assert_eq!(stats.uncorrected_errors(), 0);
+        assert_eq!(stats.keystrokes, 8);
+    }
+
+    #[test]
+    fn correct_words() {
+        let mut session = session("ab cd ef gh", "ab cx ef ");
+        assert_eq!(session.stats().correct_words, 2);
+
+        session.handle_input(Input::Backspace);
+        assert_eq!(session.stats().correct_words, 1);
+        session.handle_input(Input::Text(" ".to_string()));
+        assert_eq!(session.stats().correct_words, 2);
+    }
+
+    #[test]
+    fn wpm() {
+        let session = session("aaaa bbbb", "aaaa bxbb");
+        let stats = session.stats();
+        assert_eq!(stats.raw_wpm(Duration::from_secs(60)), 1.8);
+        assert_eq!(stats.net_wpm(Duration::from_secs(60)), 1.6);
+        assert_eq!(stats.raw_wpm(Duration::ZERO), 0.0);
+    }
+
+    #[test]
+    fn consistency() {
+        assert_eq!(super::consistency(&[60.0, 60.0, 60.0]), 100.0);
+        assert_eq!(super::consistency(&[]), 0.0);
+        assert_eq!(super::consistency(&[0.0, 0.0]), 0.0);
+        assert!(super::consistency(&[40.0, 80.0]) < super::consistency(&[55.0, 65.0]));
+    }
+
+    #[test]
+    fn word_records() {
+        let start = Instant::now();
+        let mut session = TypingSession::new("ab cd ef".to_string());
+        session.handle_input_at(Input::Text("ab".to_string()), start);
+        session.handle_input_at(Input::Text(" ".to_string()), start + Duration::from_secs(1));
+        session.handle_input_at(
+            Input::Text("xdd".to_string()),
+            start + Duration::from_secs(2),
+        );
+        session.handle_input_at(Input::Backspace, start + Duration::from_secs(2));
+        session.handle_input_at(Input::Text(" ".to_string()), start + Duration::from_secs(4));
+        session.handle_input_at(
+            Input::Text("e ".to_string()),
+            start + Duration::from_secs(5),
+        );
+
+        let words = session.words();
+        assert_eq!(words.len(), 2);
+        assert_eq!(words[0].target, "ab");
+        assert_eq!(words[0].typed, "ab");
+        assert_eq!(words[0].duration, Duration::from_secs(1));
+        assert_eq!(words[0].mistakes, 0);
+        assert_eq!(words[0].wpm(), 24.0);
+        assert_eq!(words[1].target, "cd");
+        assert_eq!(words[1].typed, "xd");
+        assert_eq!(words[1].first_typed, "xdd");
+        assert_eq!(words[1].start, start + Duration::from_secs(2));
+        assert_eq!(words[1].duration, Duration::from_secs(2));
+        assert_eq!(words[1].mistakes, 2);
+    }
+
+    #[test]
+    fn word_record_with_missed_chars() {
+        let session = session("abc de", "a ");
+        assert_eq!(session.words()[0].target, "abc");
+        assert_eq!(session.words()[0].typed, "a");
+        assert_eq!(session.words()[0].mistakes, 1);
+    }
+
+    #[test]
+    fn backspace_reopens_word_record() {
+        let mut session = session("ab cd", "ax ");
+        session.handle_input(Input::Backspace);
+        assert!(session.words().is_empty());
+        session.handle_input(Input::Backspace);
+        session.handle_input(Input::Text("b ".to_string()));
+
+        assert_eq!(session.words()[0].typed, "ab");
+        assert_eq!(session.words()[0].first_typed, "ax");
+        assert_eq!(session.words()[0].mistakes, 1);
+    }
+
+    #[test]
+    fn keystroke_log() {
+        let mut session = session("ab cd", "xb ");
+        session.handle_input(Input::Backspace);
+
+        let log = session
+            .keystroke_log()
+            .iter()
+            .map(|keystroke| {
+                (
+                    keystroke.typed.as_deref(),
+                    keystroke.expected.as_deref(),
+                    keystroke.correct,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            log,
+            [
+                (Some("x"), Some("a"), false),
+                (Some("b"), Some("b"), true),
+                (Some(" "), Some(" "), true),
+                (None, None, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn undo_removes_latest_text() {
+        let mut session = TypingSession::new("ab cd ef".to_string());
+        session.handle_input(Input::Text("ab ".to_string()));
+        session.handle_input(Input::Text("xd ".to_string()));
+        session.handle_input(Input::Backspace);
+        session.handle_input(undo());
+        assert_eq!(session.head(), 3);
+        assert_eq!(runs(&session), [(RunKind::Correct, "ab ")]);
+        assert_eq!(session.words().len(), 1);
+        assert_eq!(session.stats().corrected_errors, 1);
+        assert_eq!(session.stats().keystrokes, 8);
+
+        session.handle_input(undo());
+        assert_eq!(session.head(), 0);
+        session.handle_input(undo());
+        assert_eq!(session.head(), 0);
+    }
+
+    #[test]
+    fn stroke_is_one_keystroke() {
+        let mut session = TypingSession::new("cats dog".to_string());
+        session.handle_input(Input::Stroke {
+            undo: 0,
+            texts: vec!["cat ".to_string()],
+        });
+        session.handle_input(Input::Stroke {
+            undo: 1,
+            texts: vec!["cats".to_string(), " ".to_string()],
+        });
+        assert_eq!(session.head(), 5);
+        assert_eq!(runs(&session), [(RunKind::Correct, "cats ")]);
+        assert_eq!(session.stats().keystrokes, 2);
+
+        session.handle_input(Input::Stroke {
+            undo: 2,
+            texts: Vec::new(),
+        });
+        assert_eq!(session.head(), 0);
+        assert_eq!(session.stats().keystrokes, 3);
+    }
+
+    #[test]
+    fn undo_skipped_word() {
+        let mut session = TypingSession::new("abc de".to_string());
+        session.handle_input(Input::Text("a".to_string()));
+        session.handle_input(Input::Text(" ".to_string()));
+        session.handle_input(undo());
+        assert_eq!(session.head(), 1);
+        assert_eq!(runs(&session), [(RunKind::Correct, "a")]);
+        assert_eq!(session.stats().char_counts.missed, 0);
+    }
+
+    fn undo() -> Input {
+        Input::Stroke {
+            undo: 1,
+            texts: Vec::new(),
+        }
+    }
+
+    fn grapheme() -> impl Strategy<Value = String> {
+        prop::sample::select(&GRAPHEMES[..]).prop_map(str::to_string)
+    }
+
+    fn target_text() -> impl Strategy<Value = String> {
+        prop::collection::vec(prop::collection::vec(grapheme(), 1..5), 1..8).prop_map(|words| {
+            words
+                .into_iter()
+                .map(|word| word.concat())
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+    }
+
+    fn inputs() -> impl Strategy<Value = Vec<Input>> {
+        prop::collection::vec(
+            prop_oneof![
+                1 => Just(Input::Backspace),
+                1 => Just(undo()),
+                1 => Just(Input::Text(" ".to_string())),
+                4 => grapheme().prop_map(Input::Text),
+                1 => prop::collection::vec(grapheme(), 1..4)
+                    .prop_map(|word| Input::Text(word.concat() + " ")),
+            ],
+            0..40,
+        )
+    }
+
+    fn assert_invariants(session: &TypingSession) {
+        let text = session.text();
+
+        // Runs cover the typed text
+        let mut end = 0;
+        let mut last_kind = None;
+        for (kind, run) in session.runs() {
+            assert_eq!(run.start, end);
+            assert!(!run.is_empty());
+            assert_ne!(Some(kind), last_kind);
+            end = run.end;
+            last_kind = Some(kind);
+        }
+        assert_eq!(end, session.head());
+
+        assert!(text.is_char_boundary(session.head()));
+
+        // Counts match the runs
+        let mut counts = CharCounts::default();
+        for (kind, run) in session.runs() {
+            *counts.get_mut(kind) += text[run].graphemes(true).count();
+        }
+        assert_eq!(counts, session.stats().char_counts);
+    }
+
+    proptest! {
+        #[test]
+        fn invariants_hold(text in target_text(), inputs in inputs()) {
+            let mut session = TypingSession::new(text);
+            for input in inputs {
+                session.handle_input(input);
+                assert_invariants(&session);
+            }
+        }
+
+        #[test]
+        fn backspace_restores_text(text in target_text(), inputs in inputs()) {
+            let mut session = TypingSession::new(text.clone());
+            for input in inputs {
+                session.handle_input(input);
+            }
+            while session.head() != 0 {
+                session.handle_input(Input::Backspace);
+            }
+
+            prop_assert_eq!(session.text(), text);
+            prop_assert_eq!(session.runs().count(), 0);
+            prop_assert_eq!(session.stats().char_counts, CharCounts::default());
+            prop_assert_eq!(session.stats().correct_words, 0);
+            prop_assert!(session.words().is_empty());
+        }
+
+        #[test]
+        fn typing_target_is_correct(text in target_text()) {
+            let session = session(&text, &text);
+            prop_assert_eq!(runs(&session), [(RunKind::Correct, text.as_str())]);
+            prop_assert_eq!(
+                session.stats().correct_keystrokes,
+                text.graphemes(true).count()
+            );
+            prop_assert_eq!(session.stats().accuracy(), 100.0);
+            prop_assert_eq!(session.stats().correct_words, text.split(' ').count() - 1);
+        }
+
+        #[test]
+        fn fruncate_keeps_invariants(text in target_text(), inputs in inputs()) {
+            let mut session = TypingSession::new(text);
+            for input in inputs {
+                session.handle_input(input);
+            }
+
+            let fruncate_idx = session.active_word().start;
+            let suffix = session.text()[fruncate_idx..].to_string();
+            let stats = session.stats();
+            session.fruncate(fruncate_idx);
+
+            prop_assert_eq!(session.text(), suffix);
+            prop_assert_eq!(session.stats(), stats);
+            prop_assert_eq!(
+                session.runs().last().map_or(0, |(_, run)| run.end),
+                session.head()
+            );
+            prop_assert!(session.text().is_char_boundary(session.head()));
+        }
+    }
+}